@@ -0,0 +1,362 @@
+/// Auctions of collateral, e.g. erc721 tokens, held in escrow. The lot is everything locked for
+/// the auction's id, so a module that locked tokens for its own reason (e.g. a debt) can
+/// auction them off without releasing them first. Token owners can auction their own tokens too.
+/// Proceeds pay the auction's claims in order, and what's left goes to its residual account.
+///
+/// English auctions go to the highest bid when bidding ends. Dutch auctions go to whoever
+/// takes them first, at a price that falls every block from a start price to a floor.
+/// Lots nobody bids on or takes go to the auction's fallback account.
+
+use support::{decl_module, decl_storage, decl_event, StorageValue, StorageMap, dispatch::Result, ensure};
+use system::ensure_signed;
+use super::collateral::Collateral;
+use parity_codec::{Encode, Decode};
+use runtime_primitives::traits::{Hash, Zero, As};
+use rstd::{cmp, result};
+use rstd::prelude::*;
+
+use support::traits::{Currency, ReservableCurrency};
+#[cfg(feature = "std")]
+use serde_derive::{Serialize, Deserialize};
+
+pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+pub type AuctionOf<T> = Auction<<T as system::Trait>::AccountId, BalanceOf<T>, <T as system::Trait>::BlockNumber>;
+
+#[cfg(test)]
+mod test;
+
+/// The module's configuration trait.
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	type Currency: ReservableCurrency<Self::AccountId>;
+	/// Where lots are locked while they're auctioned, e.g. the erc721 module
+	type Collateral: Collateral<Self::AccountId, Self::Hash, AssetId = Self::Hash>;
+	/// Told when an auction settles, e.g. the debt module closing a liquidated debt
+	type OnSettle: OnSettle<Self::Hash, BalanceOf<Self>>;
+}
+
+/// Hook for modules that start auctions
+pub trait OnSettle<Hash, Balance> {
+	/// `proceeds` is none if the lot went unsold, to the auction's fallback account
+	fn on_settle(auction_id: &Hash, proceeds: Option<Balance>);
+}
+
+impl<Hash, Balance> OnSettle<Hash, Balance> for () {
+	fn on_settle(_: &Hash, _: Option<Balance>) {}
+}
+
+/// Sells off the tokens escrowed for `lot`, e.g. the collateral of a defaulted debt
+pub trait Liquidator<AccountId, Hash, Balance> {
+	/// Auctions the lot under its own id. `claims` are paid out of the proceeds first,
+	/// `residual` gets the rest, and `fallback` gets the lot if it doesn't sell.
+	/// Dutch auctions are priced off what the claims add up to
+	fn liquidate(lot: Hash, style: AuctionStyle, claims: Vec<(AccountId, Balance)>, residual: AccountId, fallback: AccountId) -> Result;
+}
+
+impl<AccountId, Hash, Balance> Liquidator<AccountId, Hash, Balance> for () {
+	fn liquidate(_: Hash, _: AuctionStyle, _: Vec<(AccountId, Balance)>, _: AccountId, _: AccountId) -> Result {
+		Err("Liquidation is not supported")
+	}
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum AuctionStyle {
+	English,
+	Dutch,
+}
+
+/// How a Dutch auction's price falls every block
+#[derive(Encode, Decode, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum Decay {
+	/// By this share of the start price, 100 is 1%
+	Linear(u64),
+	/// By this share of the previous block's price
+	Exponential(u64),
+}
+
+impl Default for Decay {
+	fn default() -> Self {
+		Decay::Linear(0)
+	}
+}
+
+/// How an auction finds its price
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum AuctionKind<Balance> {
+	/// The highest bid when bidding ends
+	English,
+	/// The first taker, at a price falling from `start_price` to `floor`
+	Dutch { start_price: Balance, floor: Balance, decay: Decay },
+}
+
+impl<Balance> Default for AuctionKind<Balance> {
+	fn default() -> Self {
+		AuctionKind::English
+	}
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Auction<AccountId, Balance, BlockNumber> {
+	pub kind: AuctionKind<Balance>,
+	pub claims: Vec<(AccountId, Balance)>,	// paid out of the proceeds first, in order
+	pub residual: AccountId,					// gets what's left of the proceeds
+	pub fallback: AccountId,					// gets the lot if it doesn't sell
+	pub start: BlockNumber,
+	pub end: BlockNumber,						// last block bids are taken, or the lot can be taken
+	pub best_bid: Option<(AccountId, Balance)>,	// reserved from the bidder until outbid
+}
+
+/// Rates are in basis points: 100 is 1%
+const RATE_DENOMINATOR: u64 = 10_000;
+/// Fixed point precision of the exponential decay factor
+const FIXED_ONE: u128 = 1_000_000_000;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Auction {
+		Auctions get(get_auction): map T::Hash => Option<Auction<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+		// How many blocks auctions take bids for
+		AuctionLength get(auction_length) config(): T::BlockNumber;
+		Nonce: u64;
+
+		// How liquidations are priced in Dutch auctions, as shares of what the claims add up to.
+		// 10_000 is all of it, so a markup above that starts the price higher than what's owed
+		DutchMarkup get(dutch_markup) config(): u64;
+		DutchFloor get(dutch_floor) config(): u64;
+		DutchDecay get(dutch_decay) config(): Decay;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event<T>() = default;
+
+		// Token owners can auction a token off, the proceeds and an unsold token are theirs
+		pub fn create_auction(origin, token_id: T::Hash, kind: AuctionKind<BalanceOf<T>>) {
+			let seller = ensure_signed(origin)?;
+			Self::ensure_kind(&kind)?;
+
+			let nonce = <Nonce<T>>::get();
+			let auction_id = (<system::Module<T>>::random_seed(), &seller, token_id, nonce)
+				.using_encoded(<T as system::Trait>::Hashing::hash);
+
+			ensure!(!<Auctions<T>>::exists(auction_id), "This lot is already being auctioned");
+			T::Collateral::lock(&seller, token_id, auction_id)?;
+			<Nonce<T>>::put(nonce + 1);
+
+			Self::start(auction_id, kind, Vec::new(), seller.clone(), seller)?;
+		}
+
+		// Bidders reserve their bid, and get it back once they're outbid
+		pub fn bid(origin, auction_id: T::Hash, value: BalanceOf<T>) {
+			let bidder = ensure_signed(origin)?;
+			let mut auction = Self::get_auction(auction_id).ok_or("This auction does not exist")?;
+
+			ensure!(auction.kind == AuctionKind::English, "Only English auctions take bids");
+			ensure!(<system::Module<T>>::block_number() <= auction.end, "This auction has ended");
+			ensure!(!value.is_zero(), "Bid cannot be zero");
+			if let Some((_, best)) = auction.best_bid {
+				ensure!(value > best, "Bid is lower than the best bid");
+			}
+
+			T::Currency::reserve(&bidder, value)?;
+			if let Some((outbid, best)) = auction.best_bid.take() {
+				T::Currency::unreserve(&outbid, best);
+			}
+
+			auction.best_bid = Some((bidder.clone(), value));
+			<Auctions<T>>::insert(auction_id, auction);
+
+			Self::deposit_event(RawEvent::Bid(bidder, auction_id, value));
+		}
+
+		// Anyone can buy the lot of a Dutch auction at its current price
+		pub fn take(origin, auction_id: T::Hash) {
+			let buyer = ensure_signed(origin)?;
+			let auction = Self::get_auction(auction_id).ok_or("This auction does not exist")?;
+			let now = <system::Module<T>>::block_number();
+
+			ensure!(now <= auction.end, "This auction has ended");
+			let price = Self::price(&auction, now).ok_or("Only Dutch auctions can be taken")?;
+			let payouts = Self::payouts(&auction, price)?;
+
+			// the lot is released last, once everyone is paid
+			T::Currency::reserve(&buyer, price)?;
+			Self::pay_out(&buyer, &payouts)?;
+			T::Collateral::release(auction_id, &buyer)?;
+
+			<Auctions<T>>::remove(auction_id);
+			Self::deposit_event(RawEvent::AuctionSettled(auction_id, Some(buyer), price));
+			T::OnSettle::on_settle(&auction_id, Some(price));
+		}
+
+		// Anyone can settle an auction once it has ended
+		pub fn settle(origin, auction_id: T::Hash) {
+			let _ = ensure_signed(origin)?;
+			let auction = Self::get_auction(auction_id).ok_or("This auction does not exist")?;
+			ensure!(<system::Module<T>>::block_number() > auction.end, "This auction has not ended yet");
+
+			let proceeds = match auction.best_bid.clone() {
+				Some((buyer, value)) => {
+					let payouts = Self::payouts(&auction, value)?;
+					Self::pay_out(&buyer, &payouts)?;
+					T::Collateral::release(auction_id, &buyer)?;
+					Self::deposit_event(RawEvent::AuctionSettled(auction_id, Some(buyer), value));
+					Some(value)
+				},
+				None => {
+					T::Collateral::release(auction_id, &auction.fallback)?;
+					Self::deposit_event(RawEvent::AuctionSettled(auction_id, None, Zero::zero()));
+					None
+				},
+			};
+
+			<Auctions<T>>::remove(auction_id);
+			T::OnSettle::on_settle(&auction_id, proceeds);
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The price a Dutch auction can be taken at in block `now`, none for English auctions
+	pub fn price(auction: &AuctionOf<T>, now: T::BlockNumber) -> Option<BalanceOf<T>> {
+		match auction.kind {
+			AuctionKind::English => None,
+			AuctionKind::Dutch { start_price, floor, decay } => {
+				let start: u64 = start_price.as_();
+				let blocks: u64 = (now - auction.start.clone()).as_();
+
+				let price = match decay {
+					Decay::Linear(rate) => {
+						let drop = (start as u128 * rate as u128).saturating_mul(blocks as u128) / RATE_DENOMINATOR as u128;
+						start.saturating_sub(cmp::min(drop, start as u128) as u64)
+					},
+					Decay::Exponential(rate) => decayed(start, rate, blocks),
+				};
+				Some(cmp::max(<BalanceOf<T> as As<u64>>::sa(price), floor))
+			},
+		}
+	}
+
+	// Splits what the lot sold for between the claims, then the residual account.
+	// Fails if any of them couldn't be paid, before anything is
+	fn payouts(auction: &AuctionOf<T>, value: BalanceOf<T>) -> result::Result<Vec<(T::AccountId, BalanceOf<T>)>, &'static str> {
+		let mut payouts = Vec::new();
+		let mut left = value;
+		for (claimant, claim) in auction.claims.iter() {
+			let paid = cmp::min(*claim, left);
+			if !paid.is_zero() {
+				payouts.push((claimant.clone(), paid));
+			}
+			left = left - paid;
+		}
+		if !left.is_zero() {
+			payouts.push((auction.residual.clone(), left));
+		}
+
+		// reserved funds only go to accounts that exist
+		for (payee, _) in payouts.iter() {
+			ensure!(!T::Currency::total_balance(payee).is_zero(), "A payee of this auction does not exist");
+		}
+		Ok(payouts)
+	}
+
+	// Pays the buyer's reserved bid out
+	fn pay_out(buyer: &T::AccountId, payouts: &[(T::AccountId, BalanceOf<T>)]) -> Result {
+		for (payee, value) in payouts.iter() {
+			T::Currency::repatriate_reserved(buyer, payee, *value)?;
+		}
+		Ok(())
+	}
+
+	// A Dutch auction's price needs somewhere to fall from, and can't fall by more than all of it
+	fn ensure_kind(kind: &AuctionKind<BalanceOf<T>>) -> Result {
+		if let AuctionKind::Dutch { start_price, floor, decay } = kind {
+			ensure!(!start_price.is_zero(), "A Dutch auction cannot start at zero");
+			ensure!(floor <= start_price, "The floor cannot be above the start price");
+			let rate = match decay {
+				Decay::Linear(rate) | Decay::Exponential(rate) => *rate,
+			};
+			ensure!(rate <= RATE_DENOMINATOR, "The decay rate cannot be above 100%");
+		}
+		Ok(())
+	}
+
+	// Opens the auction of whatever is escrowed for `lot`
+	fn start(
+			lot: T::Hash,
+			kind: AuctionKind<BalanceOf<T>>,
+			claims: Vec<(T::AccountId, BalanceOf<T>)>,
+			residual: T::AccountId,
+			fallback: T::AccountId
+	) -> Result {
+		ensure!(!<Auctions<T>>::exists(lot), "This lot is already being auctioned");
+		ensure!(!T::Collateral::locked_by(lot).is_empty(), "There is nothing to auction");
+
+		let start = <system::Module<T>>::block_number();
+		let end = start.clone() + Self::auction_length();
+		<Auctions<T>>::insert(lot, Auction { kind, claims, residual, fallback, start, end, best_bid: None });
+		Self::deposit_event(RawEvent::AuctionStarted(lot));
+
+		Ok(())
+	}
+}
+
+// `price` decayed by `rate` every block, for `blocks` blocks
+fn decayed(price: u64, rate: u64, blocks: u64) -> u64 {
+	if rate >= RATE_DENOMINATOR {
+		return 0;
+	}
+
+	// (1 - rate)^blocks, by squaring
+	let mut factor = (RATE_DENOMINATOR - rate) as u128 * FIXED_ONE / RATE_DENOMINATOR as u128;
+	let mut result = FIXED_ONE;
+	let mut n = blocks;
+	while n > 0 && result > 0 {
+		if n & 1 == 1 {
+			result = result * factor / FIXED_ONE;
+		}
+		factor = factor * factor / FIXED_ONE;
+		n >>= 1;
+	}
+
+	(price as u128 * result / FIXED_ONE) as u64
+}
+
+impl<T: Trait> Liquidator<T::AccountId, T::Hash, BalanceOf<T>> for Module<T> {
+	fn liquidate(lot: T::Hash, style: AuctionStyle, claims: Vec<(T::AccountId, BalanceOf<T>)>, residual: T::AccountId, fallback: T::AccountId) -> Result {
+		let kind = match style {
+			AuctionStyle::English => AuctionKind::English,
+			AuctionStyle::Dutch => {
+				let owed = claims.iter().fold(0u128, |owed, (_, claim)| {
+					let claim: u64 = claim.as_();
+					owed + claim as u128
+				});
+				let share = |rate: u64| <BalanceOf<T> as As<u64>>::sa((owed * rate as u128 / RATE_DENOMINATOR as u128) as u64);
+				AuctionKind::Dutch {
+					start_price: share(Self::dutch_markup()),
+					floor: share(cmp::min(Self::dutch_floor(), Self::dutch_markup())),
+					decay: Self::dutch_decay(),
+				}
+			},
+		};
+		Self::ensure_kind(&kind)?;
+		Self::start(lot, kind, claims, residual, fallback)
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		Balance = BalanceOf<T>,
+	{
+		AuctionStarted(Hash),
+		Bid(AccountId, Hash, Balance),
+		// buyer and price, no buyer if the lot went to the fallback account
+		AuctionSettled(Hash, Option<AccountId>, Balance),
+	}
+);
@@ -0,0 +1,226 @@
+#[cfg(test)]
+
+use super::*;
+use super::super::erc721;
+use support::{impl_outer_origin, assert_ok, assert_noop};
+use runtime_io::with_externalities;
+use primitives::{H256, Blake2Hasher};
+use runtime_primitives::{
+	BuildStorage,
+	traits::{IdentityLookup, BlakeTwo256},
+	testing::{Digest, DigestItem, Header}
+};
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+impl system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type Digest = Digest;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type Log = DigestItem;
+}
+
+impl balances::Trait for Test {
+	type Balance = u64;
+	type OnFreeBalanceZero = ();
+	type OnNewAccount = ();
+	type Event = ();
+	type TransactionPayment = ();
+	type TransferPayment = ();
+	type DustRemoval = ();
+}
+
+impl erc721::Trait for Test {
+	type Event = ();
+	type EscrowAuthority = ();
+	type OnTransfer = ();
+}
+
+impl Trait for Test {
+	type Event = ();
+	type Currency = balances::Module<Test>;
+	type Collateral = erc721::Module<Test>;
+	type OnSettle = ();
+}
+
+type Auction = Module<Test>;
+type Balance = balances::Module<Test>;
+type ERC = erc721::Module<Test>;
+type System = system::Module<Test>;
+
+fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+	let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+	t.extend(balances::GenesisConfig::<Test>{
+		balances: vec![(0, 100),(1, 100),(2, 100),(3, 100)],
+		transaction_base_fee: 0,
+		transaction_byte_fee: 0,
+		transfer_fee: 0,
+		creation_fee: 0,
+		existential_deposit: 0,
+		vesting: vec![],
+	}.build_storage().unwrap().0);
+	t.extend(GenesisConfig::<Test>{
+		auction_length: 2,
+		dutch_markup: 15_000,
+		dutch_floor: 5_000,
+		dutch_decay: Decay::Linear(1_000),
+	}.build_storage().unwrap().0);
+	t.into()
+}
+
+// Escrows a new token of account 0 for `lot`, and auctions it: 1 is owed 50, the rest goes to 0
+fn auction(lot: H256) -> H256 {
+	ERC::create_token(Origin::signed(0));
+	let token_id = ERC::token_of_owner_by_index((0, ERC::balance_of(0) - 1));
+	assert_ok!(ERC::lock(&0, token_id, lot));
+	assert_ok!(Auction::liquidate(lot, AuctionStyle::English, vec![(1, 50)], 0, 1));
+	token_id
+}
+
+#[test]
+fn outbid_bidders_are_refunded() {
+	with_externalities(&mut new_test_ext(), || {
+		let lot = H256::from([1u8; 32]);
+		auction(lot);
+
+		assert_ok!(Auction::bid(Origin::signed(2), lot, 40));
+		assert_eq!(Balance::reserved_balance(&2), 40);
+		assert_noop!(Auction::bid(Origin::signed(3), lot, 40), "Bid is lower than the best bid");
+
+		assert_ok!(Auction::bid(Origin::signed(3), lot, 41));
+		assert_eq!(Balance::reserved_balance(&2), 0);
+		assert_eq!(Balance::free_balance(&2), 100);
+		assert_eq!(Auction::get_auction(lot).unwrap().best_bid, Some((3, 41)));
+	});
+}
+
+#[test]
+fn settles_once_bidding_ends() {
+	with_externalities(&mut new_test_ext(), || {
+		let lot = H256::from([1u8; 32]);
+		let token_id = auction(lot);
+		assert_noop!(Auction::liquidate(lot, AuctionStyle::English, vec![], 0, 0), "This lot is already being auctioned");
+		assert_ok!(Auction::bid(Origin::signed(2), lot, 70));
+
+		System::set_block_number(2);
+		assert_noop!(Auction::settle(Origin::signed(2), lot), "This auction has not ended yet");
+
+		System::set_block_number(3);
+		assert_noop!(Auction::bid(Origin::signed(3), lot, 80), "This auction has ended");
+		assert_ok!(Auction::settle(Origin::signed(3), lot));
+
+		assert_eq!(ERC::owner_of(token_id), Some(2));
+		assert_eq!(Auction::get_auction(lot), None);
+		// 50 pays the claim, the other 20 is the residual's
+		assert_eq!(Balance::free_balance(&1), 150);
+		assert_eq!(Balance::free_balance(&0), 120);
+		assert_eq!(Balance::total_balance(&2), 30);
+	});
+}
+
+#[test]
+fn unsold_lot_goes_to_fallback() {
+	with_externalities(&mut new_test_ext(), || {
+		let lot = H256::from([1u8; 32]);
+		assert_noop!(Auction::liquidate(lot, AuctionStyle::English, vec![], 0, 1), "There is nothing to auction");
+		let token_id = auction(lot);
+
+		System::set_block_number(3);
+		assert_ok!(Auction::settle(Origin::signed(0), lot));
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+	});
+}
+
+#[test]
+fn dutch_auction_is_taken_at_falling_price() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(0));
+		let token_id = ERC::token_by_index(0);
+		assert_noop!(Auction::create_auction(Origin::signed(0), token_id,
+			AuctionKind::Dutch { start_price: 10, floor: 20, decay: Decay::Linear(1_000) }),
+			"The floor cannot be above the start price");
+		assert_noop!(Auction::create_auction(Origin::signed(0), token_id,
+			AuctionKind::Dutch { start_price: 0, floor: 0, decay: Decay::Linear(1_000) }),
+			"A Dutch auction cannot start at zero");
+		assert_noop!(Auction::create_auction(Origin::signed(0), token_id,
+			AuctionKind::Dutch { start_price: 100, floor: 40, decay: Decay::Linear(10_001) }),
+			"The decay rate cannot be above 100%");
+		assert_noop!(Auction::create_auction(Origin::signed(0), token_id,
+			AuctionKind::Dutch { start_price: 100, floor: 40, decay: Decay::Exponential(10_001) }),
+			"The decay rate cannot be above 100%");
+		assert_ok!(Auction::create_auction(Origin::signed(0), token_id,
+			AuctionKind::Dutch { start_price: 100, floor: 40, decay: Decay::Linear(1_000) }));
+
+		let auction_id = ERC::escrow_of(token_id).unwrap();
+		let auction = Auction::get_auction(auction_id).unwrap();
+		assert_eq!(Auction::price(&auction, 1), Some(90));
+		assert_eq!(Auction::price(&auction, 7), Some(40));
+		assert_noop!(Auction::bid(Origin::signed(1), auction_id, 50), "Only English auctions take bids");
+
+		System::set_block_number(2);
+		assert_ok!(Auction::take(Origin::signed(1), auction_id));
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+		assert_eq!(Auction::get_auction(auction_id), None);
+		assert_eq!(Balance::free_balance(&1), 20);
+		assert_eq!(Balance::free_balance(&0), 180);
+	});
+}
+
+#[test]
+fn dutch_liquidation_is_priced_off_claims() {
+	with_externalities(&mut new_test_ext(), || {
+		let lot = H256::from([1u8; 32]);
+		ERC::create_token(Origin::signed(0));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(ERC::lock(&0, token_id, lot));
+		assert_ok!(Auction::liquidate(lot, AuctionStyle::Dutch, vec![(1, 50)], 0, 1));
+
+		// starts at 150% of the 50 owed, and won't go below 50%
+		let auction = Auction::get_auction(lot).unwrap();
+		assert_eq!(auction.kind, AuctionKind::Dutch { start_price: 75, floor: 25, decay: Decay::Linear(1_000) });
+		assert_eq!(Auction::price(&auction, 1), Some(68));
+
+		// unsold, it goes to the fallback account
+		System::set_block_number(3);
+		assert_noop!(Auction::take(Origin::signed(2), lot), "This auction has ended");
+		assert_ok!(Auction::settle(Origin::signed(2), lot));
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+	});
+}
+
+#[test]
+fn lot_is_kept_until_everyone_can_be_paid() {
+	with_externalities(&mut new_test_ext(), || {
+		// the residual goes to account 9, which doesn't exist
+		let lot = H256::from([1u8; 32]);
+		ERC::create_token(Origin::signed(0));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(ERC::lock(&0, token_id, lot));
+		assert_ok!(Auction::liquidate(lot, AuctionStyle::Dutch, vec![(1, 50)], 9, 1));
+
+		System::set_block_number(2);
+		assert_noop!(Auction::take(Origin::signed(2), lot), "A payee of this auction does not exist");
+		assert_eq!(ERC::escrow_of(token_id), Some(lot));
+		assert_eq!(Balance::free_balance(&2), 100);
+		assert!(Auction::get_auction(lot).is_some());
+	});
+}
+
+#[test]
+fn exponential_decay() {
+	assert_eq!(decayed(1_000, 1_000, 0), 1_000);
+	assert_eq!(decayed(1_000, 1_000, 2), 810);
+	assert_eq!(decayed(1_000, 10_000, 1), 0);
+}
@@ -0,0 +1,32 @@
+/// Collateral: a common interface for locking assets against an obligation.
+/// `erc721` implements it for tokens, so the debt module (or auctions, insurance, ...)
+/// doesn't need to know what kind of asset backs it
+
+use support::dispatch::{Result, Parameter};
+use rstd::prelude::*;
+
+/// Assets that can be locked for a `reason`, e.g. a debt id. Several assets can be locked
+/// for the same reason, they have no owner until they are all released to an account
+pub trait Collateral<AccountId, Reason> {
+	/// Identifies a lockable asset, e.g. a token id
+	type AssetId: Parameter;
+
+	/// Whether `owner` could lock `asset` right now
+	fn can_lock(owner: &AccountId, asset: &Self::AssetId) -> bool;
+
+	/// Takes `asset` from `owner` and adds it to the assets locked for `reason`
+	fn lock(owner: &AccountId, asset: Self::AssetId, reason: Reason) -> Result;
+
+	/// Gives every asset locked for `reason` to `to`, or none if any of them can't be
+	fn release(reason: Reason, to: &AccountId) -> Result;
+
+	/// Locks `new`, which `owner` holds, in the place of `old` among the assets locked for
+	/// `reason`, and gives `old` to `owner`. Neither changes hands if the swap can't go through
+	fn substitute(owner: &AccountId, old: Self::AssetId, new: Self::AssetId, reason: Reason) -> Result;
+
+	/// The assets locked for `reason`
+	fn locked_by(reason: Reason) -> Vec<Self::AssetId>;
+
+	/// Whether `asset` is locked for any reason
+	fn is_locked(asset: Self::AssetId) -> bool;
+}
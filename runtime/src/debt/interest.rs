@@ -0,0 +1,141 @@
+/// Interest models: how much interest a debt owes at a point in time
+
+use support::dispatch::Result;
+use parity_codec::{Encode, Decode};
+use runtime_primitives::traits::{As, Zero};
+use rstd::result;
+
+use super::{Trait, DebtOf, BalanceOf, DebtStatus};
+
+/// Interest rates are in basis points: 100 is 1%
+pub(super) const RATE_DENOMINATOR: u128 = 10_000;
+/// Fixed point precision of the per-period growth factor used for compounding
+const FIXED_ONE: u128 = 1_000_000_000;
+
+/// How interest is charged every `interest_period`
+#[derive(Encode, Decode, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum InterestType {
+	/// A=P(1+rt): interest is only charged on the principal
+	Simple,
+	/// A=P(1+r)^t: unpaid interest is charged interest in later periods
+	Compound,
+}
+
+impl Default for InterestType {
+	fn default() -> Self {
+		InterestType::Simple
+	}
+}
+
+/// Computes the interest owed on a debt. Runtimes can supply their own model,
+/// e.g. step-up or utilization based rates, through `debt::Trait::InterestModel`
+pub trait InterestModel<T: Trait> {
+	/// Accrues interest on `debt` up to `now`, updating its `interest` and `n_periods`
+	fn accrue(debt: &mut DebtOf<T>, now: T::Moment) -> Result;
+
+	/// The interest `debt` will owe at `at`, without modifying it
+	fn quote(debt: &DebtOf<T>, at: T::Moment) -> result::Result<BalanceOf<T>, &'static str>;
+}
+
+/// The default model: simple or compound interest charged every full `interest_period`
+/// since `term_start`, as picked by the debt's `interest_type`
+pub struct PeriodicInterest;
+
+impl PeriodicInterest {
+	// returns the total interest owed at `at`, and the number of periods it covers
+	fn accrued<T: Trait>(debt: &DebtOf<T>, at: T::Moment) -> result::Result<(u64, u64), &'static str> {
+		let prev_interest: u64 = debt.interest.as_();
+
+		// only fulfilled, outstanding debts accrue interest
+		let outstanding = debt.status == DebtStatus::Active || debt.status == DebtStatus::Defaulted;
+		if !outstanding || at < debt.term_start || debt.interest_period.is_zero() {
+			return Ok((prev_interest, debt.n_periods));
+		}
+
+		let elapsed: u64 = (at - debt.term_start.clone()).as_();
+		let period: u64 = debt.interest_period.as_();
+
+		// additional periods to calculate interest for
+		let t = (elapsed / period).saturating_sub(debt.n_periods);
+		let principal: u64 = debt.principal.as_();
+
+		let accrued = match debt.interest_type {
+			InterestType::Simple => simple_interest(principal, debt.interest_rate, t)?,
+			InterestType::Compound => {
+				let balance = principal.checked_add(prev_interest)
+					.ok_or("Overflow adding interest to principal")?;
+				compound_interest(balance, debt.interest_rate, t)?
+			},
+		};
+		let new_interest = prev_interest.checked_add(accrued)
+			.ok_or("Overflow adding accrued interest")?;
+
+		Ok((new_interest, debt.n_periods + t))
+	}
+}
+
+impl<T: Trait> InterestModel<T> for PeriodicInterest {
+	fn accrue(debt: &mut DebtOf<T>, now: T::Moment) -> Result {
+		let (interest, n_periods) = Self::accrued::<T>(debt, now)?;
+
+		debt.interest = <BalanceOf<T> as As<u64>>::sa(interest);
+		debt.n_periods = n_periods;
+
+		Ok(())
+	}
+
+	fn quote(debt: &DebtOf<T>, at: T::Moment) -> result::Result<BalanceOf<T>, &'static str> {
+		let (interest, _) = Self::accrued::<T>(debt, at)?;
+		Ok(<BalanceOf<T> as As<u64>>::sa(interest))
+	}
+}
+
+// simple interest calculation: A=P(1+rt), returns the interest part P*r*t
+pub(super) fn simple_interest(balance: u64, rate: u64, periods: u64) -> result::Result<u64, &'static str> {
+	let interest = (balance as u128).checked_mul(rate as u128)
+		.and_then(|x| x.checked_mul(periods as u128))
+		.ok_or("Overflow calculating simple interest")? / RATE_DENOMINATOR;
+	to_u64(interest)
+}
+
+// compound interest calculation: A=P(1+r)^t, returns the interest part A-P
+// (1+r)^t is raised by squaring as a fixed point number, so it is cheap for any t
+pub(super) fn compound_interest(balance: u64, rate: u64, periods: u64) -> result::Result<u64, &'static str> {
+	if balance == 0 || rate == 0 || periods == 0 {
+		return Ok(0);
+	}
+
+	let mut factor = (rate as u128).checked_mul(FIXED_ONE)
+		.map(|x| x / RATE_DENOMINATOR)
+		.and_then(|x| x.checked_add(FIXED_ONE))
+		.ok_or("Overflow calculating interest growth factor")?;
+	let mut growth = FIXED_ONE;
+	let mut n = periods;
+	while n > 0 {
+		if n & 1 == 1 {
+			growth = fixed_mul(growth, factor)?;
+		}
+		n >>= 1;
+		if n > 0 {
+			factor = fixed_mul(factor, factor)?;
+		}
+	}
+
+	let amount = (balance as u128).checked_mul(growth)
+		.ok_or("Overflow calculating compound interest")? / FIXED_ONE;
+	to_u64(amount - balance as u128)
+}
+
+fn fixed_mul(a: u128, b: u128) -> result::Result<u128, &'static str> {
+	a.checked_mul(b)
+		.map(|x| x / FIXED_ONE)
+		.ok_or("Overflow calculating compound interest")
+}
+
+fn to_u64(x: u128) -> result::Result<u64, &'static str> {
+	if x > u64::max_value() as u128 {
+		return Err("Interest overflows the balance type");
+	}
+	Ok(x as u64)
+}
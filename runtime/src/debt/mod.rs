@@ -10,51 +10,223 @@
 use support::{decl_module, decl_storage, decl_event, StorageValue, StorageMap, dispatch::Result, ensure};
 use system::ensure_signed;
 use super::erc721;
-use rstd::cmp;
+use super::auction::{self, AuctionStyle, Liquidator};
+use super::collateral::Collateral;
+use super::oracle::Appraiser;
+use rstd::{cmp, result};
 use parity_codec::{Encode, Decode};
-use runtime_primitives::traits::{Hash, Zero, As, CheckedSub};
+use runtime_primitives::traits::{Hash, Zero, As, CheckedAdd, CheckedSub};
+use rstd::prelude::*;
 
-use support::traits::Currency;
+use support::traits::{Currency, ReservableCurrency};
+use self::erc721::TokenIssuer;
+#[cfg(feature = "std")]
+use serde_derive::{Serialize, Deserialize};
 
-type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+pub type AssetOf<T> = <<T as Trait>::Collateral as Collateral<<T as system::Trait>::AccountId, <T as system::Trait>::Hash>>::AssetId;
+pub type DebtOf<T> = Debt<<T as system::Trait>::AccountId, BalanceOf<T>, <T as timestamp::Trait>::Moment>;
+
+mod interest;
+pub use self::interest::{InterestModel, InterestType, PeriodicInterest};
+use self::interest::{simple_interest, RATE_DENOMINATOR};
 
 #[cfg(test)]
 mod test;
 
 /// The module's configuration trait.
-pub trait Trait: timestamp::Trait + erc721::Trait {
+pub trait Trait: timestamp::Trait {
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
-	type Currency: Currency<Self::AccountId>;
+	type Currency: ReservableCurrency<Self::AccountId>;
+	/// What debts are secured by, e.g. erc721 tokens
+	type Collateral: Collateral<Self::AccountId, Self::Hash>;
+	/// Issues the notes that represent creditor positions
+	type Notes: erc721::TokenIssuer<Self::AccountId, Self::Hash>;
+	/// How interest accrues on debts, `PeriodicInterest` by default
+	type InterestModel: InterestModel<Self>;
+	/// Auctions off the collateral of liquidated debts, `()` if debts can only be seized
+	type Liquidator: Liquidator<Self::AccountId, Self::Hash, BalanceOf<Self>>;
+	/// Values locked assets for loan-to-value checks, `()` if nothing is appraised
+	type Appraiser: Appraiser<AssetOf<Self>, BalanceOf<Self>>;
+}
+
+/// Where a debt is in its lifecycle. Extrinsics only act on debts in the status they expect
+#[derive(Encode, Decode, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum DebtStatus {
+	Requested,	// waiting for a creditor to fulfill it
+	Active,		// fulfilled, being repaid
+	Repaid,		// paid off, collateral returned to the requestor
+	Defaulted,	// missed a deadline and its grace period, collateral can be seized
+	Seized,		// collateral went to the creditor
+	Cancelled,	// withdrawn by the requestor before it was fulfilled
+	Expired,	// nobody fulfilled it before request_expiry
+	Liquidating,	// defaulted or margin called, collateral is being auctioned off
+	Liquidated,	// the auction settled, its proceeds went to the creditor
+}
+
+impl Default for DebtStatus {
+	fn default() -> Self {
+		DebtStatus::Requested
+	}
+}
+
+impl DebtStatus {
+	/// Whether a debt in this status may move to `next`
+	pub fn can_become(&self, next: DebtStatus) -> bool {
+		use self::DebtStatus::*;
+		match (*self, next) {
+			(Requested, Active) | (Requested, Cancelled) | (Requested, Expired) => true,
+			(Expired, Cancelled) => true,
+			(Active, Repaid) | (Active, Defaulted) | (Active, Liquidating) => true,
+			(Defaulted, Seized) | (Defaulted, Liquidating) => true,
+			(Liquidating, Liquidated) => true,
+			_ => false,
+		}
+	}
 }
 
 // Asset owners can create a DebtRequest to ask for a traunche of Balance
 #[derive(Encode, Decode, Default, Clone, PartialEq)] //these are custom traits required by all structs (some traits forenums)
 #[cfg_attr(feature = "std", derive(Debug))] // attr provided by rust compiler. uses derive(debug) trait when in std mode
 pub struct Debt<AccountId, Balance, Moment> {   //Needs the blake2 Hash trait
-	request_expiry: Moment,	// debt_request 
+	pub request_expiry: Moment,	// debt_request 
 
-	requestor: AccountId,		// TODO: Use Option<T::AccountId>?
-	beneficiary: AccountId,	// Recipient of the loan
-	creditor: AccountId,
+	pub requestor: AccountId,		// TODO: Use Option<T::AccountId>?
+	pub beneficiary: AccountId,	// Recipient of the loan
+	pub creditor: AccountId,
+	pub status: DebtStatus,
 	
 	//TODO: refactor out debt-terms attributes
-	term_start: Moment,				// when the debt was fulfilled & loanded
-	term_length: Moment, 			// total time *interval* to repay, in seconds. not a date.
+	pub term_start: Moment,				// when the debt was fulfilled & loanded
+	pub term_length: Moment, 			// total time *interval* to repay, in seconds. not a date.
+
+	pub principal: Balance,				// principal remaining
+	pub interest: Balance,				// interest remaining
+	pub interest_rate: u64,				// interest: 100 is 1% , significance to 0.00%
+	pub interest_period: Moment,	// monthly, daily, in seconds
+	pub interest_type: InterestType,	// simple or compounding every period
+	pub n_periods: u64, 					// n periods of interest already calculated in interest
+
+	pub grace_period: Moment,		// how long after a missed deadline repayment is still accepted
+	pub late_fee: Balance,				// late fees remaining, paid before interest
+	pub late: bool,						// a deadline was missed and the fee for it charged
 
-	principal: Balance,				// principal remaining
-	interest: Balance,				// interest remaining
-	interest_rate: u64,				// interest: 100 is 1% , significance to 0.00%
-	interest_period: Moment,	// monthly, daily, in seconds
-	n_periods: u64, 					// n periods of interest already calculated in interest
+	pub prepayment: PrepaymentTerms<Balance, Moment>,
+	pub interest_paid: Balance,		// to the current creditor, counts towards the minimum interest
 }
 
+/// What repaying principal early costs. All zero, the default, means it can be prepaid freely
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PrepaymentTerms<Balance, Moment> {
+	pub minimum_interest: Balance,	// interest the creditor earns even if the debt is prepaid
+	pub penalty_rate: u64,				// charged on principal prepaid, 100 is 1%
+	pub lockout: Moment,					// offset from term_start before which principal can't be prepaid
+}
+
+/// One of the equal repayments of an amortizing debt
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Installment<Balance, Moment> {
+	pub due: Moment,			// offset from term_start, the schedule is set before the debt is fulfilled
+	pub amount: Balance,		// principal due by then
+	pub paid: Balance,			// principal paid towards it so far
+}
+
+/// A creditor's standing offer to lend on these terms to anyone who locks collateral.
+/// The principal stays reserved from the creditor until the offer is accepted or cancelled
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct LoanOffer<AccountId, Balance, Moment> {
+	pub creditor: AccountId,
+	pub offer_expiry: Moment,
+	pub principal: Balance,
+	pub interest_rate: u64,
+	pub interest_period: Moment,
+	pub interest_type: InterestType,
+	pub term_length: Moment,
+}
+
+/// New terms the borrower asks for on an active debt, taken up by its creditor as an
+/// extension, or by a new creditor refinancing it
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TermsProposal<Moment> {
+	pub term_length: Moment,	// from the original term_start, at least the current term
+	pub interest_rate: u64,
+}
+
+/// The parts an account can play in a debt, debts are indexed per account and role
+#[derive(Encode, Decode, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum DebtRole {
+	Requestor,
+	Beneficiary,
+	Creditor,
+}
+
+pub type LoanOfferOf<T> = LoanOffer<<T as system::Trait>::AccountId, BalanceOf<T>, <T as timestamp::Trait>::Moment>;
+
 type DebtIndex = u64;
+type OfferIndex = u64;
+
+/// Upper bound on installments per debt, keeps the schedule cheap to walk
+const MAX_INSTALLMENTS: u32 = 360;
+/// Upper bound on debts accrued in a block, the rest wait for the next one
+const MAX_ACCRUALS_PER_BLOCK: u32 = 32;
+/// Upper bound on accrual buckets read in a block, the cursor catches up over the next ones
+const MAX_ACCRUAL_BUCKETS_PER_BLOCK: u32 = 16;
+/// Span of moments an accrual bucket covers, a minute
+const ACCRUAL_BUCKET: u64 = 60;
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Debt {		
 		Debts get(get_debt): map T::Hash => Debt<T::AccountId, BalanceOf<T>, T::Moment>;
 		DebtIndexToId get(get_debt_id): map DebtIndex => T::Hash;
 		DebtCount get(get_total_debts): DebtIndex;
+		// Repayment schedule of amortizing debts, empty for single repayment debts
+		Schedules get(get_schedule): map T::Hash => Vec<Installment<BalanceOf<T>, T::Moment>>;
+		// Requestor's balance reserved as collateral, on top of (or instead of) locked assets
+		CollateralBalance get(get_collateral_balance): map T::Hash => BalanceOf<T>;
+
+		// Open loan offers, removed once accepted or cancelled
+		Offers get(get_offer): map T::Hash => Option<LoanOffer<T::AccountId, BalanceOf<T>, T::Moment>>;
+		OfferIndexToId get(get_offer_id): map OfferIndex => T::Hash;
+		OfferCount get(get_total_offers): OfferIndex;
+		// What each open offer reserved of its creditor's balance. The creditor's reserve also
+		// holds balance collateral and auction bids, so only this much is the offer's
+		OfferReserves get(get_offer_reserve): map T::Hash => BalanceOf<T>;
+		// Open debts of every account, per role. Debts leave once they're repaid, seized or cancelled
+		AccountDebtCount get(get_account_debt_count): map (T::AccountId, DebtRole) => u64;
+		AccountDebts get(get_account_debt_by_index): map (T::AccountId, DebtRole, u64) => T::Hash;
+		AccountDebtIndex: map (T::Hash, DebtRole) => u64;
+		// (next interest period boundary, debt_id) of outstanding debts, bucketed by the minute
+		// the boundary falls in and keyed by (bucket, position), so a block only reads the entries
+		// it accrues. Buckets before the cursor are done, and positions before a bucket's head
+		AccrualQueue get(get_accrual_entry): map (u64, u64) => Option<(T::Moment, T::Hash)>;
+		AccrualHead get(get_accrual_head): map u64 => u64;
+		AccrualTail get(get_accrual_tail): map u64 => u64;
+		AccrualCursor get(get_accrual_cursor): u64;
+		AccrualCount get(get_accrual_count): u64;
+		// The borrower's latest proposal for new terms, per debt
+		Proposals get(get_proposal): map T::Hash => Option<TermsProposal<T::Moment>>;
+
+		// Policy for missed deadlines. Debts keep the grace period they were requested with
+		GracePeriod get(grace_period) config(): T::Moment;
+		// Charged once per missed deadline, on top of a share of what's outstanding
+		LateFee get(late_fee) config(): BalanceOf<T>;
+		LateFeeRate get(late_fee_rate) config(): u64;	// 100 is 1%, like interest rates
+		// Most a debt can borrow against its collateral's appraised value, zero to not check
+		MaxLoanToValue get(max_loan_to_value) config(): u64;	// 7_000 is 70%
+		// Active debts owing more than this share of their collateral's value can be margin called,
+		// zero for no margin calls. Borrowers get the margin call period to add collateral or repay
+		MaintenanceLoanToValue get(maintenance_loan_to_value) config(): u64;
+		MarginCallPeriod get(margin_call_period) config(): T::Moment;
+		// Deadline of every open margin call
+		MarginCalls get(get_margin_call): map T::Hash => Option<T::Moment>;
+		// (old, new) collateral the creditor agreed to swap, per debt
+		SubstitutionApprovals get(get_substitution_approval): map T::Hash => Option<(AssetOf<T>, AssetOf<T>)>;
 	}
 }
 
@@ -63,6 +235,72 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event<T>() = default;
 
+		// Accrue the debts whose interest period ended, so storage never shows stale interest.
+		// The timestamp isn't set yet, so this catches up to the previous block's time
+		fn on_initialize(_n: T::BlockNumber) {
+			let now = <timestamp::Module<T>>::get();
+			let last = Self::accrual_bucket_of(now.clone());
+			let start = Self::get_accrual_cursor();
+
+			// with nothing queued there's nothing to walk through
+			if Self::get_accrual_count() == 0 {
+				if start != last {
+					<AccrualCursor<T>>::put(last);
+				}
+				return;
+			}
+
+			let mut cursor = start;
+			let mut budget = MAX_ACCRUALS_PER_BLOCK;
+			for _ in 0..MAX_ACCRUAL_BUCKETS_PER_BLOCK {
+				// entries queued into this bucket while it's walked wait for the next block
+				let start_head = Self::get_accrual_head(cursor);
+				let tail = Self::get_accrual_tail(cursor);
+				let mut head = start_head;
+
+				// taken in the order they were queued, a later one can wait until the minute is up
+				while head < tail && budget > 0 {
+					let (at, debt_id) = match Self::get_accrual_entry((cursor, head)) {
+						Some(entry) => entry,
+						None => { head += 1; continue; },
+					};
+					if at > now {
+						break;
+					}
+					<AccrualQueue<T>>::remove((cursor, head));
+					<AccrualCount<T>>::mutate(|n| *n -= 1);
+					head += 1;
+					budget -= 1;
+
+					if Self::update_balance(debt_id).is_err() {
+						continue;
+					}
+					// repaid and seized debts drop out of the queue
+					let debt = Self::get_debt(debt_id);
+					if debt.status == DebtStatus::Active || debt.status == DebtStatus::Defaulted {
+						Self::enqueue_accrual(debt_id, &debt);
+					}
+				}
+
+				if head == Self::get_accrual_tail(cursor) {
+					<AccrualHead<T>>::remove(cursor);
+					<AccrualTail<T>>::remove(cursor);
+				} else if head != start_head {
+					<AccrualHead<T>>::insert(cursor, head);
+				}
+
+				// earlier buckets are drained unless the budget ran out
+				if cursor >= last || budget == 0 {
+					break;
+				}
+				cursor += 1;
+			}
+
+			if cursor != start {
+				<AccrualCursor<T>>::put(cursor);
+			}
+		}
+
 		pub fn borrow(
 				origin, 
 				beneficiary: T::AccountId, // why do let beneficiary = T::Lookup::lookup(beneficiary)?;
@@ -70,28 +308,139 @@ decl_module! {
 				principal: BalanceOf<T>, //make compact?
 				interest_rate: u64,
 				interest_period: T::Moment,
-				term_length: T::Moment
+				term_length: T::Moment,
+				interest_type: InterestType,
+				installments: u32 	// n equal repayments of principal over the term, 0 or 1 to repay at term end
 		) {
 			let requestor = ensure_signed(origin)?;	
-			let now = <timestamp::Module<T>>::get();
+			Self::_borrow(requestor, beneficiary, request_expiry, principal, interest_rate,
+				interest_period, term_length, interest_type, installments)?;
+		}
 
-			let debt_id = (<system::Module<T>>::random_seed(), &requestor, now).using_encoded(<T as system::Trait>::Hashing::hash);
-	
-			ensure!(!<Debts<T>>::exists(debt_id), "Error: Debt request already exists");
-			ensure!(! interest_period.is_zero(), "Error: interest period cannot be zero");
-			ensure!(! term_length.is_zero(), "Error: term length cannot be zero");
-			ensure!( term_length > interest_period, "Error: interest period cannot be longer than term length");
+		// Same as `borrow`, and escrows the token for the new debt in the same call.
+		// Fails without creating the debt if the token can't be collateralized
+		pub fn borrow_against(
+				origin,
+				token_id: AssetOf<T>,
+				beneficiary: T::AccountId,
+				request_expiry: T::Moment,
+				principal: BalanceOf<T>,
+				interest_rate: u64,
+				interest_period: T::Moment,
+				term_length: T::Moment,
+				interest_type: InterestType,
+				installments: u32
+		) {
+			let requestor = ensure_signed(origin)?;
+			ensure!(T::Collateral::can_lock(&requestor, &token_id), "You cannot lock this asset");
+			Self::ensure_loan_to_value(principal, &[token_id.clone()], Zero::zero())?;
 
-			let i = Self::get_total_debts();
-			<DebtCount<T>>::put(i+1);
+			let debt_id = Self::_borrow(requestor.clone(), beneficiary, request_expiry, principal, interest_rate,
+				interest_period, term_length, interest_type, installments)?;
+			T::Collateral::lock(&requestor, token_id, debt_id)?;
+		}
 
-			<DebtIndexToId<T>>::insert(i, debt_id);
+		// Requestors can secure an open request with their own balance, which stays reserved
+		// until the debt is repaid (or goes to the creditor if it's seized).
+		// Active debts can take more, e.g. to meet a margin call
+		pub fn collateralize_balance(origin, debt_id: T::Hash, value: BalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			let debt = <Debts<T>>::get(debt_id);
+
+			ensure!(debt.requestor == sender, "Only the requestor can collateralize this debt");
+			ensure!(debt.status == DebtStatus::Requested || debt.status == DebtStatus::Active,
+				"This debt request is no longer open");
+			ensure!(!value.is_zero(), "Collateral cannot be zero");
+
+			let collateral = Self::get_collateral_balance(debt_id).checked_add(&value)
+				.ok_or("Overflow adding to collateral balance")?;
+			T::Currency::reserve(&sender, value)?;
+			<CollateralBalance<T>>::insert(debt_id, collateral);
+
+			Self::deposit_event(RawEvent::BalanceCollateralized(sender, debt_id, value));
+			Self::check_margin_call(debt_id, &debt);
+		}
+
+		// Borrowers can lock another asset for an active debt, e.g. to meet a margin call
+		pub fn add_collateral(origin, debt_id: T::Hash, token_id: AssetOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			let debt = <Debts<T>>::get(debt_id);
+
+			ensure!(debt.requestor == sender, "Only the requestor can collateralize this debt");
+			ensure!(debt.status == DebtStatus::Active, "Only active debts can take more collateral");
+			ensure!(T::Collateral::can_lock(&sender, &token_id), "You cannot lock this asset");
+
+			T::Collateral::lock(&sender, token_id, debt_id)?;
+
+			Self::deposit_event(RawEvent::CollateralAdded(sender, debt_id));
+			Self::check_margin_call(debt_id, &debt);
+		}
+
+		// Creditors can agree to take one asset as collateral instead of another, whatever they're worth.
+		// A new approval replaces the previous one
+		pub fn approve_substitution(origin, debt_id: T::Hash, old_token_id: AssetOf<T>, new_token_id: AssetOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			let debt = <Debts<T>>::get(debt_id);
+
+			ensure!(debt.creditor == sender, "Only the creditor can approve a substitution");
+			ensure!(debt.status == DebtStatus::Active, "Only active debts can substitute collateral");
+			ensure!(T::Collateral::locked_by(debt_id).contains(&old_token_id), "This asset is not collateral for this debt");
+
+			<SubstitutionApprovals<T>>::insert(debt_id, (old_token_id, new_token_id));
+
+			Self::deposit_event(RawEvent::SubstitutionApproved(sender, debt_id));
+		}
+
+		// Borrowers can swap a locked asset for another one they hold, e.g. to sell it, and get
+		// the old one back. Without the creditor's approval, the new asset has to be appraised
+		// at least as high as the old one
+		pub fn substitute_collateral(origin, debt_id: T::Hash, old_token_id: AssetOf<T>, new_token_id: AssetOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			Self::update_balance(debt_id)?;
+			let debt = <Debts<T>>::get(debt_id);
 
-			<Debts<T>>::insert(debt_id, Debt { requestor: requestor.clone(), beneficiary, request_expiry, 
-																				principal,interest_rate, interest_period, term_length, ..Default::default() }
-			);
+			ensure!(debt.requestor == sender, "Only the requestor can substitute collateral");
+			ensure!(debt.status == DebtStatus::Active, "Only active debts can substitute collateral");
+			ensure!(T::Collateral::locked_by(debt_id).contains(&old_token_id), "This asset is not collateral for this debt");
+			ensure!(T::Collateral::can_lock(&sender, &new_token_id), "You cannot lock this asset");
 
-			Self::deposit_event(RawEvent::DebtBorrowed(requestor, debt_id));
+			let approval = (old_token_id.clone(), new_token_id.clone());
+			if Self::get_substitution_approval(debt_id) != Some(approval) {
+				let old_value = T::Appraiser::value_of(&old_token_id).ok_or("The collateral has no current appraisal")?;
+				let new_value = T::Appraiser::value_of(&new_token_id).ok_or("The collateral has no current appraisal")?;
+				ensure!(new_value >= old_value, "The new collateral is worth less than the old one");
+			}
+
+			T::Collateral::substitute(&sender, old_token_id, new_token_id, debt_id)?;
+			<SubstitutionApprovals<T>>::remove(debt_id);
+
+			Self::deposit_event(RawEvent::CollateralSubstituted(sender, debt_id));
+			Self::check_margin_call(debt_id, &debt);
+		}
+
+		// Requestors can set prepayment terms on an open request, for creditors who want
+		// to protect their yield
+		pub fn set_prepayment_terms(
+				origin,
+				debt_id: T::Hash,
+				minimum_interest: BalanceOf<T>,
+				penalty_rate: u64,
+				lockout: T::Moment
+		) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			let mut debt = <Debts<T>>::get(debt_id);
+
+			ensure!(debt.requestor == sender, "Only the requestor can set prepayment terms");
+			ensure!(debt.status == DebtStatus::Requested, "This debt request is no longer open");
+			ensure!(lockout <= debt.term_length, "The lockout cannot be longer than the term");
+
+			debt.prepayment = PrepaymentTerms { minimum_interest, penalty_rate, lockout };
+			<Debts<T>>::insert(debt_id, debt);
 		}
 
 		// Creditor sends money into this function to fulfill loan
@@ -100,122 +449,1001 @@ decl_module! {
 			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
 			let mut debt = <Debts<T>>::get(debt_id);
 
-			let now = <timestamp::Module<T>>::get();
-			ensure!(debt.request_expiry >= now, "This debt request has expired");
-			ensure!(debt.creditor == <T as system::Trait>::AccountId::default(), "This debt request is fulfilled");
-			
-			let collateral = <erc721::Module<T>>::get_escrow(debt_id);
-			ensure!(collateral != <T as system::Trait>::Hash::default(), "This debt is not collateralized");
+			Self::ensure_open_request(&debt)?;
+			ensure!(Self::is_collateralized(debt_id), "This debt is not collateralized");
+			Self::ensure_debt_loan_to_value(debt_id, &debt)?;
 			
 			T::Currency::transfer(&sender, &debt.beneficiary, debt.principal)?;
-			debt.creditor = sender.clone();
-			debt.term_start = now;
-			<Debts<T>>::insert(debt_id, debt);
+			Self::activate(debt_id, &mut debt, sender.clone())?;
 			
 			Self::deposit_event(RawEvent::DebtFulfilled(sender, debt_id));
 		}
 
-		// Debtors can repay on a debt
-		pub fn repay(origin, debt_id: T::Hash, value: BalanceOf<T>) {
-			Self::update_balance(debt_id);
+		// Requestors can withdraw a request nobody fulfilled, even once it expired,
+		// and get their collateral back out of escrow
+		pub fn cancel(origin, debt_id: T::Hash) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			let mut debt = <Debts<T>>::get(debt_id);
+
+			ensure!(debt.requestor == sender, "Only the requestor can cancel this debt request");
+			ensure!(debt.status == DebtStatus::Requested || debt.status == DebtStatus::Expired,
+				"This debt request is no longer open");
+
+			Self::set_status(debt_id, &mut debt, DebtStatus::Cancelled)?;
+			Self::close(debt_id, &debt);
+			<Debts<T>>::insert(debt_id, debt);
 
+			Self::release_collateral(debt_id, &sender, &sender)?;
+
+			Self::deposit_event(RawEvent::DebtCancelled(sender, debt_id));
+		}
+
+		// Creditors can post a standing offer, reserving the principal until someone accepts it
+		pub fn offer(
+				origin,
+				offer_expiry: T::Moment,
+				principal: BalanceOf<T>,
+				interest_rate: u64,
+				interest_period: T::Moment,
+				term_length: T::Moment,
+				interest_type: InterestType
+		) {
+			let creditor = ensure_signed(origin)?;
+			let now = <timestamp::Module<T>>::get();
+			let i = Self::get_total_offers();
+
+			let offer_id = (<system::Module<T>>::random_seed(), &creditor, now, i).using_encoded(<T as system::Trait>::Hashing::hash);
+
+			ensure!(!<Offers<T>>::exists(offer_id), "Error: Loan offer already exists");
+			ensure!(!principal.is_zero(), "Error: principal cannot be zero");
+			Self::ensure_terms(&interest_period, &term_length, 1)?;
+
+			T::Currency::reserve(&creditor, principal)?;
+
+			<OfferCount<T>>::put(i+1);
+			<OfferIndexToId<T>>::insert(i, offer_id);
+			<Offers<T>>::insert(offer_id, LoanOffer { creditor: creditor.clone(), offer_expiry, principal,
+				interest_rate, interest_period, interest_type, term_length });
+			<OfferReserves<T>>::insert(offer_id, principal);
+
+			Self::deposit_event(RawEvent::LoanOffered(creditor, offer_id));
+		}
+
+		// Creditors can withdraw their offer at any time before it's accepted
+		pub fn cancel_offer(origin, offer_id: T::Hash) {
 			let sender = ensure_signed(origin)?;
+			let offer = Self::get_offer(offer_id).ok_or("This loan offer does not exist")?;
+			ensure!(offer.creditor == sender, "Only the creditor can cancel this loan offer");
+
+			<Offers<T>>::remove(offer_id);
+			T::Currency::unreserve(&sender, <OfferReserves<T>>::take(offer_id));
+
+			Self::deposit_event(RawEvent::OfferCancelled(sender, offer_id));
+		}
+
+		// Asset owners can take an offer by locking collateral for it. The debt is created
+		// already fulfilled, and the reserved principal is paid out in the same call
+		pub fn accept_offer(origin, offer_id: T::Hash, token_id: AssetOf<T>) {
+			let borrower = ensure_signed(origin)?;
+			let offer = Self::get_offer(offer_id).ok_or("This loan offer does not exist")?;
 			let now = <timestamp::Module<T>>::get();
-			
+
+			ensure!(offer.offer_expiry >= now, "This loan offer has expired");
+			ensure!(T::Collateral::can_lock(&borrower, &token_id), "You cannot lock this asset");
+			Self::ensure_loan_to_value(offer.principal, &[token_id.clone()], Zero::zero())?;
+			ensure!(Self::is_offer_funded(offer_id, &offer), "The creditor's funds are no longer reserved");
+			ensure!(!T::Currency::total_balance(&borrower).is_zero(), "The beneficiary account does not exist");
+			ensure!(!<Debts<T>>::exists(Self::next_debt_id(&borrower)), "Error: Debt request already exists");
+
+			// nothing is written until the principal has moved
+			T::Currency::repatriate_reserved(&offer.creditor, &borrower, offer.principal)?;
+			<Offers<T>>::remove(offer_id);
+			<OfferReserves<T>>::remove(offer_id);
+
+			let debt_id = Self::_borrow(borrower.clone(), borrower.clone(), now.clone(), offer.principal, offer.interest_rate,
+				offer.interest_period, offer.term_length, offer.interest_type, 1)?;
+			T::Collateral::lock(&borrower, token_id, debt_id)?;
+
+			let mut debt = Self::get_debt(debt_id);
+			Self::activate(debt_id, &mut debt, offer.creditor.clone())?;
+
+			Self::deposit_event(RawEvent::OfferAccepted(borrower, offer_id, debt_id));
+			Self::deposit_event(RawEvent::DebtFulfilled(offer.creditor, debt_id));
+		}
+
+		// Debtors can repay on a debt
+		pub fn repay(origin, debt_id: T::Hash, value: BalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
 			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
-			
+			Self::update_balance(debt_id)?;
+
 			let mut debt = <Debts<T>>::get(debt_id);
-			ensure!(debt.creditor != <T as system::Trait>::AccountId::default(), "This debt was never fulfilled");
-			
-			let term_end = debt.term_start.clone() + debt.term_length.clone(); // TODO figure out safer way to add Moments 
-			ensure!(now <= term_end, "This debt is past due");
+			Self::ensure_repayable(&debt)?;
 		
-			let balance = debt.principal + debt.interest; 
-			let payment = cmp::min(value, balance); 			// make sure debtor doesn't overpay
-			
-			T::Currency::transfer(&sender, &debt.creditor, payment)?;
-			
-			// 1. Substrate from interest first
-			let interest_payment = cmp::min(debt.interest, payment);
+			let now = <timestamp::Module<T>>::get();
+			let due_principal = Self::principal_due(debt_id, &debt, now.clone());
+
+			let owed_now = debt.late_fee.checked_add(&debt.interest)
+				.and_then(|b| b.checked_add(&due_principal))
+				.ok_or("Overflow calculating what's owed")?;
+
+			// paying more than is owed so far prepays principal, on the debt's prepayment terms
+			if debt.principal > due_principal && value > owed_now {
+				ensure!(now >= debt.term_start.clone() + debt.prepayment.lockout.clone(), "This debt cannot be prepaid yet");
+
+				// paying it all off early, the creditor earns at least the minimum interest
+				let (_, _, payoff) = Self::quote_outstanding(debt_id, now.clone())?;
+				if value >= payoff {
+					debt.interest = Self::with_minimum_interest(&debt, debt.interest)?;
+				}
+			}
+			let mut left = value;
+
+			// 1. Late fees first
+			let fee_payment = cmp::min(debt.late_fee, left);
+			debt.late_fee = debt.late_fee - fee_payment;
+			left = left - fee_payment;
+
+			// 2. Then substract from interest
+			let interest_payment = cmp::min(debt.interest, left);
 			debt.interest = debt.interest.checked_sub(&interest_payment)
 				.ok_or("Underflow substracting interest payment")?;
+			debt.interest_paid = debt.interest_paid + interest_payment;
+			left = left - interest_payment;
+
+			// 3. Then principal that's due, and what's left prepays principal plus its penalty
+			let due_payment = cmp::min(due_principal, left);
+			left = left - due_payment;
+			let (prepayment, penalty) = Self::prepay(&debt, debt.principal - due_payment, left)?;
+			left = left - prepayment - penalty;
 
-			// 2. If money left, substract from the principal
-			if payment > interest_payment {
-				let principal_payment = payment.checked_sub(&interest_payment)
-					.ok_or("Underflow substracting principal payment")?;
+			let principal_payment = due_payment + prepayment;
+
+			// whatever is left over isn't charged, make sure debtor doesn't overpay.
+			// The creditor is whoever holds the debt's note
+			T::Currency::transfer(&sender, &debt.creditor, value - left)?;
+
+			if !principal_payment.is_zero() {
 				debt.principal = debt.principal.checked_sub(&principal_payment)
 					.ok_or("Underflow substracting from principal")?;				
+				Self::pay_installments(debt_id, principal_payment);
+			}
+
+			if debt.principal.is_zero() && debt.interest.is_zero() && debt.late_fee.is_zero() {
+				Self::set_status(debt_id, &mut debt, DebtStatus::Repaid)?;
 			}
 
 			<Debts<T>>::insert(debt_id, debt.clone());
 
-			if debt.principal.is_zero() && debt.interest.is_zero() {
-				<erc721::Module<T>>::uncollateralize_token(debt.requestor, debt_id)?;	
+			if debt.status == DebtStatus::Repaid {
+				Self::close(debt_id, &debt);
+				Self::retire_note(debt_id)?;
+				Self::release_collateral(debt_id, &debt.requestor, &debt.requestor)?;
 			}
 
-			Self::deposit_event(RawEvent::DebtRepaid(sender, debt_id));
+			Self::deposit_event(RawEvent::DebtRepaid(sender, debt_id, fee_payment, interest_payment, principal_payment, penalty));
+			Self::check_margin_call(debt_id, &debt);
 		}
 
 		// Creditors can seize expired loans
-		pub fn seize(origin, debt_id: T::Hash) {	
-			Self::update_balance(debt_id);
+		pub fn seize(origin, debt_id: T::Hash) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			Self::update_balance(debt_id)?;
+
+			let mut debt = <Debts<T>>::get(debt_id);
+
+			match debt.status {
+				DebtStatus::Defaulted => (),
+				DebtStatus::Seized => return Err("This debt has already been seized"),
+				DebtStatus::Repaid => return Err("This debt has been paid off"),
+				DebtStatus::Active => return Err("This debt has not defaulted yet"),
+				DebtStatus::Liquidating | DebtStatus::Liquidated => return Err("This debt is being liquidated"),
+				_ => return Err("This debt request was never fulfilled"),
+			}
+			ensure!(debt.creditor == sender, "Only the creditor can seize this debt");
+
+			Self::set_status(debt_id, &mut debt, DebtStatus::Seized)?;
+			<Debts<T>>::insert(debt_id, debt.clone());
+
+			Self::close(debt_id, &debt);
+			Self::retire_note(debt_id)?;
+			Self::release_collateral(debt_id, &debt.requestor, &debt.creditor)?;
+
+			Self::deposit_event(RawEvent::DebtSeized(sender, debt_id));
+		}
 
+		// Creditors can auction off the tokens securing a defaulted debt, instead of seizing them.
+		// Anyone can, once a margin call on an active debt expired and it's still undercollateralized.
+		// What's owed is paid out of the proceeds and any surplus goes to the borrower.
+		// Reserved balance collateral goes towards what's owed right away. If it covers all of it,
+		// or there are no tokens, that settles the debt and any tokens go back to the borrower
+		pub fn liquidate(origin, debt_id: T::Hash, style: AuctionStyle) {
 			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			Self::update_balance(debt_id)?;
+			let mut debt = <Debts<T>>::get(debt_id);
 			let now = <timestamp::Module<T>>::get();
 
+			let margin_call_expired = debt.status == DebtStatus::Active
+				&& Self::get_margin_call(debt_id).map_or(false, |deadline| now > deadline);
+			if margin_call_expired {
+				ensure!(Self::is_undercollateralized(debt_id, &debt)?, "This debt's margin call was met");
+			} else {
+				ensure!(debt.status == DebtStatus::Defaulted, "This debt has not defaulted yet");
+				ensure!(debt.creditor == sender, "Only the creditor can liquidate this debt");
+			}
+
+			let owed = Self::owed(&debt)?;
+			let reserved = Self::get_collateral_balance(debt_id);
+			let covered = cmp::min(reserved, owed);
+			if !covered.is_zero() {
+				ensure!(!T::Currency::total_balance(&debt.creditor).is_zero(), "The creditor account does not exist");
+			}
+
+			// settled from the reserve when it pays it all off, or there are no tokens to auction
+			let tokens = T::Collateral::locked_by(debt_id);
+			if covered == owed || tokens.is_empty() {
+				Self::set_status(debt_id, &mut debt, DebtStatus::Liquidating)?;
+				Self::set_status(debt_id, &mut debt, DebtStatus::Liquidated)?;
+
+				if !tokens.is_empty() {
+					T::Collateral::release(debt_id, &debt.requestor)?;
+				}
+				<CollateralBalance<T>>::remove(debt_id);
+				if !covered.is_zero() {
+					T::Currency::repatriate_reserved(&debt.requestor, &debt.creditor, covered)?;
+				}
+				T::Currency::unreserve(&debt.requestor, reserved - covered);
+
+				<Debts<T>>::insert(debt_id, debt.clone());
+				Self::close(debt_id, &debt);
+				Self::retire_note(debt_id)?;
+
+				Self::deposit_event(RawEvent::DebtLiquidating(sender, debt_id));
+				Self::deposit_event(RawEvent::DebtLiquidated(debt_id, covered));
+				return Ok(());
+			}
+
+			Self::set_status(debt_id, &mut debt, DebtStatus::Liquidating)?;
+
+			// the reserve moves before the auction starts or anything is written
+			if !covered.is_zero() {
+				T::Currency::repatriate_reserved(&debt.requestor, &debt.creditor, covered)?;
+			}
+			T::Currency::unreserve(&debt.requestor, reserved - covered);
+			<CollateralBalance<T>>::remove(debt_id);
+
+			T::Liquidator::liquidate(debt_id, style, vec![(debt.creditor.clone(), owed - covered)],
+				debt.requestor.clone(), debt.creditor.clone())?;
+
+			<Proposals<T>>::remove(debt_id);
+			<MarginCalls<T>>::remove(debt_id);
+			<Debts<T>>::insert(debt_id, debt);
+			Self::retire_note(debt_id)?;
+
+			Self::deposit_event(RawEvent::DebtLiquidating(sender, debt_id));
+		}
+
+		// Anyone can margin call an active debt that owes more than the maintenance ratio of
+		// its collateral's value. If it isn't fixed by the deadline, the debt can be liquidated
+		pub fn margin_call(origin, debt_id: T::Hash) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			Self::update_balance(debt_id)?;
+			let debt = <Debts<T>>::get(debt_id);
+
+			ensure!(debt.status == DebtStatus::Active, "Only active debts can be margin called");
+			ensure!(Self::get_margin_call(debt_id).is_none(), "This debt already has a margin call");
+			ensure!(Self::is_undercollateralized(debt_id, &debt)?, "This debt is not undercollateralized");
+
+			let deadline = <timestamp::Module<T>>::get() + Self::margin_call_period();
+			<MarginCalls<T>>::insert(debt_id, deadline.clone());
+
+			Self::deposit_event(RawEvent::MarginCalled(sender, debt_id, deadline));
+		}
+
+		// Borrowers can ask for a longer term, at a new interest rate. A new proposal replaces
+		// the previous one
+		pub fn propose_extension(origin, debt_id: T::Hash, new_term_length: T::Moment, new_rate: u64) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			Self::update_balance(debt_id)?;
+			let debt = <Debts<T>>::get(debt_id);
+
+			ensure!(debt.requestor == sender, "Only the requestor can propose new terms");
+			ensure!(debt.status == DebtStatus::Active, "Only active debts can get new terms");
+			ensure!(new_term_length >= debt.term_length, "An extension cannot shorten the term");
+			let schedule = Self::get_schedule(debt_id);
+			Self::ensure_terms(&debt.interest_period, &new_term_length, schedule.len() as u32)?;
+			// what's left of the principal is respread over the unpaid installments
+			Self::ensure_installments(debt.principal, schedule.iter().filter(|i| i.paid < i.amount).count() as u32)?;
+
+			<Proposals<T>>::insert(debt_id, TermsProposal { term_length: new_term_length, interest_rate: new_rate });
+
+			Self::deposit_event(RawEvent::ExtensionProposed(sender, debt_id));
+		}
+
+		// Creditors can agree to the borrower's proposed terms
+		pub fn accept_extension(origin, debt_id: T::Hash) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			Self::update_balance(debt_id)?;
+			let mut debt = <Debts<T>>::get(debt_id);
+			let proposal = Self::get_proposal(debt_id).ok_or("There are no proposed terms for this debt")?;
+
+			ensure!(debt.creditor == sender, "Only the creditor can accept new terms");
+			ensure!(debt.status == DebtStatus::Active, "Only active debts can get new terms");
+
+			Self::apply_terms(debt_id, &mut debt, proposal);
+
+			Self::deposit_event(RawEvent::ExtensionAccepted(sender, debt_id));
+		}
+
+		// Anyone can take over a debt on the borrower's proposed terms, by paying off its
+		// creditor in full. What was owed becomes the new principal, and the collateral stays
+		// in escrow throughout
+		pub fn refinance(origin, debt_id: T::Hash) {
+			let sender = ensure_signed(origin)?;
 			ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+			Self::update_balance(debt_id)?;
 			let mut debt = <Debts<T>>::get(debt_id);
+			let proposal = Self::get_proposal(debt_id).ok_or("There are no proposed terms for this debt")?;
 
-			ensure!(debt.creditor != <T as system::Trait>::AccountId::default(), "This debt request was never fulfilled");
-			let term_end = debt.term_start.clone() + debt.term_length.clone(); // TODO figure out safer way to add Moments 
-			ensure!(now >= term_end, "This debt has not defaulted yet");
+			ensure!(debt.status == DebtStatus::Active, "Only active debts can get new terms");
+			ensure!(debt.creditor != sender, "The creditor can accept the extension instead");
+			ensure!(debt.requestor != sender, "The borrower cannot refinance their own debt");
+			// an escrowed note can't be handed over
+			ensure!(T::Notes::owner(debt_id) == Some(debt.creditor.clone()), "The creditor's note is escrowed");
 
-			let owed = debt.principal + debt.interest;
-			ensure!(! owed.is_zero(), "This debt has been paid off");
+			// paying off the old creditor early is a prepayment, on the debt's prepayment terms
+			let (_, _, payoff) = Self::quote_outstanding(debt_id, <timestamp::Module<T>>::get())?;
+			T::Currency::transfer(&sender, &debt.creditor, payoff)?;
 
-			<erc721::Module<T>>::uncollateralize_token(debt.creditor, debt_id)?;
+			T::Notes::burn(debt_id)?;
+			T::Notes::mint(&sender, debt_id)?;
 
-			Self::deposit_event(RawEvent::DebtSeized(sender, debt_id));
+			Self::change_creditor(debt_id, &mut debt, sender.clone());
+			debt.principal = payoff;
+			debt.interest = Zero::zero();
+			debt.late_fee = Zero::zero();
+			debt.interest_paid = Zero::zero();
+			// the old creditor's penalties were paid off, the new one starts on today's policy
+			debt.late = false;
+			debt.grace_period = Self::grace_period();
+			Self::apply_terms(debt_id, &mut debt, proposal);
+
+			Self::deposit_event(RawEvent::DebtRefinanced(sender, debt_id));
 		}
 	}
 }
 
 impl <T: Trait> Module<T> {
+	// Creates a debt request, returns its id
+	fn _borrow(
+			requestor: T::AccountId,
+			beneficiary: T::AccountId,
+			request_expiry: T::Moment,
+			principal: BalanceOf<T>,
+			interest_rate: u64,
+			interest_period: T::Moment,
+			term_length: T::Moment,
+			interest_type: InterestType,
+			installments: u32
+	) -> result::Result<T::Hash, &'static str> {
+		let debt_id = Self::next_debt_id(&requestor);
+	
+		ensure!(!<Debts<T>>::exists(debt_id), "Error: Debt request already exists");
+		Self::ensure_terms(&interest_period, &term_length, installments)?;
+		Self::ensure_installments(principal, installments)?;
+
+		let schedule = Self::amortize(principal, term_length.clone(), installments);
+
+		let i = Self::get_total_debts();
+		<DebtCount<T>>::put(i+1);
+
+		<DebtIndexToId<T>>::insert(i, debt_id);
+		Self::index_debt(&requestor, DebtRole::Requestor, debt_id);
+		Self::index_debt(&beneficiary, DebtRole::Beneficiary, debt_id);
+
+		<Debts<T>>::insert(debt_id, Debt { requestor: requestor.clone(), beneficiary, request_expiry, 
+																			principal,interest_rate, interest_period, interest_type, term_length,
+																			grace_period: Self::grace_period(), ..Default::default() }
+		);
+		if !schedule.is_empty() {
+			<Schedules<T>>::insert(debt_id, schedule);
+		}
+
+		Self::deposit_event(RawEvent::DebtBorrowed(requestor, debt_id));
+
+		Ok(debt_id)
+	}
+
+	// The id `requestor`'s next debt gets, there's one per requestor per block
+	fn next_debt_id(requestor: &T::AccountId) -> T::Hash {
+		let now = <timestamp::Module<T>>::get();
+		(<system::Module<T>>::random_seed(), requestor, now).using_encoded(<T as system::Trait>::Hashing::hash)
+	}
+
+	fn ensure_terms(interest_period: &T::Moment, term_length: &T::Moment, installments: u32) -> Result {
+		ensure!(! interest_period.is_zero(), "Error: interest period cannot be zero");
+		ensure!(! term_length.is_zero(), "Error: term length cannot be zero");
+		ensure!( term_length > interest_period, "Error: interest period cannot be longer than term length");
+		ensure!(installments <= MAX_INSTALLMENTS, "Error: too many installments");
+		ensure!(T::Moment::sa(installments as u64) <= *term_length, "Error: installments are shorter than a moment");
+
+		Ok(())
+	}
+
+	// Every installment has to repay some principal
+	fn ensure_installments(principal: BalanceOf<T>, installments: u32) -> Result {
+		ensure!(installments < 2 || <BalanceOf<T> as As<u64>>::sa(installments as u64) <= principal,
+			"Error: more installments than principal");
+		Ok(())
+	}
+
+	// Accrues interest, charges late fees once a deadline is missed, and moves the debt
+	// to Expired or Defaulted once its deadlines (and grace period) have passed
 	pub fn update_balance(debt_id: T::Hash) -> Result {
 		let now = <timestamp::Module<T>>::get();
 
 		ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
 		let mut debt = <Debts<T>>::get(debt_id);
 
-		let time_passed = now - (T::Moment::sa(debt.n_periods) * debt.interest_period.clone()); //TODO safer math
+		T::InterestModel::accrue(&mut debt, now.clone())?;
 
-		// additional periods to calculate interest for
-		let t:u64 = (time_passed / debt.interest_period.clone()).as_();
-		let principal:u64 = debt.principal.as_();
-		let prev_interest:u64 = debt.interest.as_();
-		
-		// simple interest calculation: A=P(1+rt)
-		let new_balance = principal * (10000 + debt.interest_rate * t) / 10000; 
-		let new_interest = prev_interest + new_balance - principal;
+		match debt.status {
+			DebtStatus::Requested if now > debt.request_expiry => {
+				Self::set_status(debt_id, &mut debt, DebtStatus::Expired)?;
+			},
+			DebtStatus::Active => match Self::missed_deadline(debt_id, &debt, now.clone()) {
+				Some(deadline) if now > deadline + debt.grace_period.clone() => {
+					Self::set_status(debt_id, &mut debt, DebtStatus::Defaulted)?;
+				},
+				Some(_) if !debt.late => {
+					let fee = Self::late_fee_for(&debt)?;
+					debt.late_fee = debt.late_fee.checked_add(&fee).ok_or("Overflow adding late fee")?;
+					debt.late = true;
+					Self::deposit_event(RawEvent::LateFeeCharged(debt_id, fee));
+				},
+				Some(_) => (),
+				// caught up, the next missed deadline is charged again
+				None => debt.late = false,
+			},
+			_ => (),
+		}
+
+		<Debts<T>>::insert(debt_id, debt);
+
+		Ok(())
+	}
+
+	/// What paying off a debt at `at` takes, as (principal, interest, total), without writing
+	/// anything. Interest includes the minimum interest, the total late fees and prepayment penalty
+	pub fn quote_outstanding(debt_id: T::Hash, at: T::Moment)
+		-> result::Result<(BalanceOf<T>, BalanceOf<T>, BalanceOf<T>), &'static str>
+	{
+		ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+		let debt = Self::get_debt(debt_id);
+		Self::ensure_repayable(&debt)?;
+
+		let mut interest = T::InterestModel::quote(&debt, at.clone())?;
+		let mut penalty = Zero::zero();
+
+		// what update_balance would have done by then
+		let mut late_fee = debt.late_fee;
+		match Self::missed_deadline(debt_id, &debt, at.clone()) {
+			Some(deadline) if at > deadline + debt.grace_period.clone() => return Err("This debt would have defaulted by then"),
+			Some(_) if !debt.late => {
+				let accrued = Debt { interest, ..debt.clone() };
+				late_fee = late_fee.checked_add(&Self::late_fee_for(&accrued)?).ok_or("Overflow adding late fee")?;
+			},
+			_ => (),
+		}
+
+		let due_principal = Self::principal_due(debt_id, &debt, at.clone());
+		if debt.principal > due_principal {
+			ensure!(at >= debt.term_start.clone() + debt.prepayment.lockout.clone(), "This debt cannot be prepaid yet");
+
+			interest = Self::with_minimum_interest(&debt, interest)?;
+			let prepaid: u64 = (debt.principal - due_principal).as_();
+			penalty = <BalanceOf<T> as As<u64>>::sa(simple_interest(prepaid, debt.prepayment.penalty_rate, 1)?);
+		}
+
+		let total = debt.principal.checked_add(&interest)
+			.and_then(|t| t.checked_add(&late_fee))
+			.and_then(|t| t.checked_add(&penalty))
+			.ok_or("Overflow calculating the payoff")?;
+
+		Ok((debt.principal, interest, total))
+	}
+
+	/// Ids of the debts `f` holds for, in the order they were requested. Reads every debt
+	/// ever created, so it's only meant for off-chain queries, never for dispatchables
+	pub fn debts_where<F: Fn(&DebtOf<T>) -> bool>(f: F) -> Vec<T::Hash> {
+		(0..Self::get_total_debts())
+			.map(Self::get_debt_id)
+			.filter(|debt_id| f(&Self::get_debt(debt_id)))
+			.collect()
+	}
+
+	pub fn debt_exists(debt_id: T::Hash) -> bool {
+		<Debts<T>>::exists(debt_id)
+	}
+
+	/// Whether an offer can fund a debt request: same principal and terms, at no more
+	/// than the interest rate the requestor asked for
+	pub fn offer_matches(debt: &DebtOf<T>, offer: &LoanOfferOf<T>) -> bool {
+		offer.principal == debt.principal
+			&& offer.term_length == debt.term_length
+			&& offer.interest_period == debt.interest_period
+			&& offer.interest_type == debt.interest_type
+			&& offer.interest_rate <= debt.interest_rate
+	}
+
+	/// Whether an offer's own reserve still covers its principal
+	pub fn is_offer_funded(offer_id: T::Hash, offer: &LoanOfferOf<T>) -> bool {
+		let reserved = Self::get_offer_reserve(offer_id);
+		reserved >= offer.principal && T::Currency::reserved_balance(&offer.creditor) >= reserved
+	}
+
+	/// Funds an open debt request out of a matching loan offer, at the offer's interest rate.
+	/// Used by the order book, nothing is written unless the whole match goes through
+	pub fn fulfill_from_offer(debt_id: T::Hash, offer_id: T::Hash) -> Result {
+		ensure!(<Debts<T>>::exists(debt_id), "This debt does not exist");
+		let mut debt = Self::get_debt(debt_id);
+		let offer = Self::get_offer(offer_id).ok_or("This loan offer does not exist")?;
+		let now = <timestamp::Module<T>>::get();
+
+		Self::ensure_open_request(&debt)?;
+		ensure!(offer.offer_expiry >= now, "This loan offer has expired");
+		ensure!(Self::offer_matches(&debt, &offer), "This loan offer does not match the debt request");
+		ensure!(Self::is_collateralized(debt_id), "This debt is not collateralized");
+		Self::ensure_debt_loan_to_value(debt_id, &debt)?;
+		ensure!(Self::is_offer_funded(offer_id, &offer), "The creditor's funds are no longer reserved");
+		ensure!(!T::Currency::total_balance(&debt.beneficiary).is_zero(), "The beneficiary account does not exist");
+
+		T::Currency::repatriate_reserved(&offer.creditor, &debt.beneficiary, offer.principal)?;
+		<Offers<T>>::remove(offer_id);
+		<OfferReserves<T>>::remove(offer_id);
+
+		debt.interest_rate = offer.interest_rate;
+		Self::activate(debt_id, &mut debt, offer.creditor.clone())?;
+
+		Self::deposit_event(RawEvent::OfferAccepted(debt.requestor, offer_id, debt_id));
+		Self::deposit_event(RawEvent::DebtFulfilled(offer.creditor, debt_id));
+
+		Ok(())
+	}
+
+	// Only active debts are repaid, defaulted ones go to the creditor instead
+	fn ensure_repayable(debt: &DebtOf<T>) -> Result {
+		match debt.status {
+			DebtStatus::Active => Ok(()),
+			DebtStatus::Defaulted | DebtStatus::Seized | DebtStatus::Liquidating | DebtStatus::Liquidated
+				=> Err("This debt is past due"),
+			DebtStatus::Repaid => Err("This debt has been paid off"),
+			_ => Err("This debt was never fulfilled"),
+		}
+	}
+
+	// Fails unless a request can still be fulfilled. Only reads, so a failed call doesn't leave
+	// the request marked Expired behind
+	fn ensure_open_request(debt: &DebtOf<T>) -> Result {
+		ensure!(debt.status != DebtStatus::Expired, "This debt request has expired");
+		ensure!(debt.status == DebtStatus::Requested, "This debt request is no longer open");
+		ensure!(<timestamp::Module<T>>::get() <= debt.request_expiry, "This debt request has expired");
+		Ok(())
+	}
+
+	/// Whether a debt is backed by locked assets or reserved balance
+	pub fn is_collateralized(debt_id: T::Hash) -> bool {
+		!T::Collateral::locked_by(debt_id).is_empty() || !Self::get_collateral_balance(debt_id).is_zero()
+	}
+
+	/// Whether a debt's collateral is currently worth enough to back its principal
+	pub fn is_within_loan_to_value(debt_id: T::Hash, debt: &DebtOf<T>) -> bool {
+		Self::ensure_debt_loan_to_value(debt_id, debt).is_ok()
+	}
+
+	/// What a debt's collateral is worth: its locked assets at their current appraisals,
+	/// plus its reserved balance. Fails if any asset has no appraisal, or only a stale one
+	pub fn collateral_value(debt_id: T::Hash) -> result::Result<BalanceOf<T>, &'static str> {
+		Self::appraise_collateral(&T::Collateral::locked_by(debt_id), Self::get_collateral_balance(debt_id))
+	}
+
+	fn appraise_collateral(assets: &[AssetOf<T>], reserved: BalanceOf<T>) -> result::Result<BalanceOf<T>, &'static str> {
+		assets.iter().try_fold(reserved, |value, asset| {
+			let appraisal = T::Appraiser::value_of(asset).ok_or("The collateral has no current appraisal")?;
+			value.checked_add(&appraisal).ok_or("Overflow valuing collateral")
+		})
+	}
+
+	// Fails if `principal` is more than the max loan-to-value ratio allows against this collateral
+	fn ensure_loan_to_value(principal: BalanceOf<T>, assets: &[AssetOf<T>], reserved: BalanceOf<T>) -> Result {
+		let max = Self::max_loan_to_value();
+		if max == 0 {
+			return Ok(());
+		}
+
+		let principal: u64 = principal.as_();
+		let value: u64 = Self::appraise_collateral(assets, reserved)?.as_();
+		ensure!(principal as u128 * RATE_DENOMINATOR <= value as u128 * max as u128,
+			"The principal is too high for the collateral's value");
+		Ok(())
+	}
+
+	fn ensure_debt_loan_to_value(debt_id: T::Hash, debt: &DebtOf<T>) -> Result {
+		Self::ensure_loan_to_value(debt.principal, &T::Collateral::locked_by(debt_id), Self::get_collateral_balance(debt_id))
+	}
+
+	// What's owed on a debt so far, late fees included
+	fn owed(debt: &DebtOf<T>) -> result::Result<BalanceOf<T>, &'static str> {
+		debt.principal.checked_add(&debt.interest)
+			.and_then(|b| b.checked_add(&debt.late_fee))
+			.ok_or("Overflow calculating what's owed")
+	}
+
+	/// What a debt owes as a share of its collateral's value, 10_000 being all of it
+	pub fn loan_to_value(debt_id: T::Hash, debt: &DebtOf<T>) -> result::Result<u64, &'static str> {
+		let owed: u64 = Self::owed(debt)?.as_();
+		let value: u64 = Self::collateral_value(debt_id)?.as_();
+		if value == 0 {
+			return Ok(if owed == 0 { 0 } else { u64::max_value() });
+		}
+		Ok(cmp::min(owed as u128 * RATE_DENOMINATOR / value as u128, u64::max_value() as u128) as u64)
+	}
+
+	// Whether a debt owes more than the maintenance ratio allows, never if margin calls are off
+	fn is_undercollateralized(debt_id: T::Hash, debt: &DebtOf<T>) -> result::Result<bool, &'static str> {
+		let maintenance = Self::maintenance_loan_to_value();
+		if maintenance == 0 {
+			return Ok(false);
+		}
+		Ok(Self::loan_to_value(debt_id, debt)? > maintenance)
+	}
+
+	// Closes a debt's margin call once it's back under the maintenance ratio.
+	// Collateral that can't be valued doesn't count as meeting it
+	fn check_margin_call(debt_id: T::Hash, debt: &DebtOf<T>) {
+		if Self::get_margin_call(debt_id).is_none() || debt.status != DebtStatus::Active {
+			return;
+		}
+		if Self::is_undercollateralized(debt_id, debt) == Ok(false) {
+			<MarginCalls<T>>::remove(debt_id);
+			Self::deposit_event(RawEvent::MarginCallMet(debt_id));
+		}
+	}
+
+	// Hands all of a debt's collateral to `to`. Reserved balance is unreserved if that is
+	// the requestor, otherwise it moves into `to`'s free balance
+	fn release_collateral(debt_id: T::Hash, requestor: &T::AccountId, to: &T::AccountId) -> Result {
+		if !T::Collateral::locked_by(debt_id).is_empty() {
+			T::Collateral::release(debt_id, to)?;
+		}
+
+		let reserved = <CollateralBalance<T>>::take(debt_id);
+		if !reserved.is_zero() {
+			if to == requestor {
+				T::Currency::unreserve(requestor, reserved);
+			} else {
+				T::Currency::repatriate_reserved(requestor, to, reserved)?;
+			}
+		}
 
-		debt.interest = <BalanceOf<T> as As<u64>>::sa(new_interest as u64); //todo get rid of extraneous things
-		debt.n_periods = debt.n_periods + t;
+		Ok(())
+	}
 
+	// Starts the term of a funded debt, and mints the creditor its note.
+	// The note's token id is the debt id
+	fn activate(debt_id: T::Hash, debt: &mut DebtOf<T>, creditor: T::AccountId) -> Result {
+		debt.creditor = creditor.clone();
+		Self::index_debt(&creditor, DebtRole::Creditor, debt_id);
+		debt.term_start = <timestamp::Module<T>>::get();
+		Self::set_status(debt_id, debt, DebtStatus::Active)?;
+		T::Notes::mint(&creditor, debt_id)?;
 		<Debts<T>>::insert(debt_id, debt.clone());
 
+		Self::enqueue_accrual(debt_id, debt);
+
 		Ok(())
 	}
+
+	/// Open debts `who` plays `role` in
+	pub fn debts_of(who: &T::AccountId, role: DebtRole) -> Vec<T::Hash> {
+		(0..Self::get_account_debt_count((who.clone(), role)))
+			.map(|i| Self::get_account_debt_by_index((who.clone(), role, i)))
+			.collect()
+	}
+
+	fn index_debt(who: &T::AccountId, role: DebtRole, debt_id: T::Hash) {
+		let count = Self::get_account_debt_count((who.clone(), role));
+
+		<AccountDebts<T>>::insert((who.clone(), role, count), debt_id);
+		<AccountDebtIndex<T>>::insert((debt_id, role), count);
+		<AccountDebtCount<T>>::insert((who.clone(), role), count + 1);
+	}
+
+	// Swaps the last of the account's debts into the removed one's place
+	fn unindex_debt(who: &T::AccountId, role: DebtRole, debt_id: T::Hash) {
+		if !<AccountDebtIndex<T>>::exists((debt_id, role)) {
+			return;
+		}
+		let last_index = Self::get_account_debt_count((who.clone(), role)) - 1;
+		let index = <AccountDebtIndex<T>>::take((debt_id, role));
+
+		if index != last_index {
+			let last_debt_id = Self::get_account_debt_by_index((who.clone(), role, last_index));
+			<AccountDebts<T>>::insert((who.clone(), role, index), last_debt_id);
+			<AccountDebtIndex<T>>::insert((last_debt_id, role), index);
+		}
+
+		<AccountDebts<T>>::remove((who.clone(), role, last_index));
+		<AccountDebtCount<T>>::insert((who.clone(), role), last_index);
+	}
+
+	// Hands an outstanding debt to a new creditor, the caller still has to store the debt
+	fn change_creditor(debt_id: T::Hash, debt: &mut DebtOf<T>, creditor: T::AccountId) {
+		Self::unindex_debt(&debt.creditor, DebtRole::Creditor, debt_id);
+		Self::index_debt(&creditor, DebtRole::Creditor, debt_id);
+		debt.creditor = creditor;
+		// the new creditor hasn't agreed to the old one's approvals
+		<SubstitutionApprovals<T>>::remove(debt_id);
+	}
+
+	// Clears what's only kept for open debts
+	fn close(debt_id: T::Hash, debt: &DebtOf<T>) {
+		<Proposals<T>>::remove(debt_id);
+		<MarginCalls<T>>::remove(debt_id);
+		<SubstitutionApprovals<T>>::remove(debt_id);
+		Self::unindex_debt(&debt.requestor, DebtRole::Requestor, debt_id);
+		Self::unindex_debt(&debt.beneficiary, DebtRole::Beneficiary, debt_id);
+		Self::unindex_debt(&debt.creditor, DebtRole::Creditor, debt_id);
+	}
+
+	// Queues a debt for accrual at the end of its current interest period. That's always
+	// after now, so never in a bucket the cursor already passed
+	fn enqueue_accrual(debt_id: T::Hash, debt: &DebtOf<T>) {
+		let at = debt.term_start.clone() + debt.interest_period.clone() * T::Moment::sa(debt.n_periods + 1);
+		let bucket = Self::accrual_bucket_of(at.clone());
+
+		let tail = Self::get_accrual_tail(bucket);
+		<AccrualQueue<T>>::insert((bucket, tail), (at, debt_id));
+		<AccrualTail<T>>::insert(bucket, tail + 1);
+		<AccrualCount<T>>::mutate(|n| *n += 1);
+	}
+
+	fn accrual_bucket_of(moment: T::Moment) -> u64 {
+		let moment: u64 = moment.as_();
+		moment / ACCRUAL_BUCKET
+	}
+
+	// Burns the note of a closed debt. A note that's escrowed somewhere is left alone,
+	// it no longer entitles its holder to anything
+	fn retire_note(debt_id: T::Hash) -> Result {
+		if T::Notes::owner(debt_id).is_some() {
+			T::Notes::burn(debt_id)?;
+		}
+		Ok(())
+	}
+
+	// Moves a debt along its lifecycle, the caller still has to store the debt
+	fn set_status(debt_id: T::Hash, debt: &mut DebtOf<T>, status: DebtStatus) -> Result {
+		ensure!(debt.status.can_become(status), "This debt cannot change to that status");
+		debt.status = status;
+
+		Self::deposit_event(RawEvent::DebtStatusChanged(debt_id, status));
+		Ok(())
+	}
+
+	/// The earliest deadline an active debt has missed: the first installment that's due and
+	/// not paid in full, or the end of the term
+	pub fn missed_deadline(debt_id: T::Hash, debt: &DebtOf<T>, now: T::Moment) -> Option<T::Moment> {
+		let missed = Self::get_schedule(debt_id).iter()
+			.map(|i| (debt.term_start.clone() + i.due.clone(), i))
+			.find(|(due, i)| now > *due && i.paid < i.amount)
+			.map(|(due, _)| due);
+
+		let term_end = debt.term_start.clone() + debt.term_length.clone(); // TODO figure out safer way to add Moments 
+		match missed {
+			Some(due) => Some(due),
+			None if now > term_end => Some(term_end),
+			None => None,
+		}
+	}
+
+	// Principal that has to be repaid by now: installments that are due, or all of it once
+	// the term ends. Repaying more than that is a prepayment
+	fn principal_due(debt_id: T::Hash, debt: &DebtOf<T>, now: T::Moment) -> BalanceOf<T> {
+		if !<Schedules<T>>::exists(debt_id) {
+			let term_end = debt.term_start.clone() + debt.term_length.clone();
+			return if now >= term_end { debt.principal } else { Zero::zero() };
+		}
+
+		Self::get_schedule(debt_id).iter()
+			.filter(|i| now >= debt.term_start.clone() + i.due.clone())
+			.fold(Zero::zero(), |due: BalanceOf<T>, i| due + (i.amount - i.paid))
+	}
+
+	// Splits `value` into the most principal it prepays, up to `principal`, and the penalty on it
+	fn prepay(debt: &DebtOf<T>, principal: BalanceOf<T>, value: BalanceOf<T>)
+		-> result::Result<(BalanceOf<T>, BalanceOf<T>), &'static str>
+	{
+		let rate = debt.prepayment.penalty_rate;
+		let value: u64 = value.as_();
+		let principal: u64 = principal.as_();
+
+		let covered = (value as u128 * RATE_DENOMINATOR / (RATE_DENOMINATOR + rate as u128)) as u64;
+		let mut prepayment = cmp::min(principal, covered);
+		// the penalty rounds down, so one more might still be covered
+		if prepayment < principal && prepayment + 1 + simple_interest(prepayment + 1, rate, 1)? <= value {
+			prepayment += 1;
+		}
+		let penalty = simple_interest(prepayment, rate, 1)?;
+
+		Ok((<BalanceOf<T> as As<u64>>::sa(prepayment), <BalanceOf<T> as As<u64>>::sa(penalty)))
+	}
+
+	// Tops `interest` up to what the debt's minimum interest still leaves the creditor to earn
+	fn with_minimum_interest(debt: &DebtOf<T>, interest: BalanceOf<T>) -> result::Result<BalanceOf<T>, &'static str> {
+		let earned = debt.interest_paid.checked_add(&interest).ok_or("Overflow calculating interest earned")?;
+		if debt.prepayment.minimum_interest > earned {
+			interest.checked_add(&(debt.prepayment.minimum_interest - earned)).ok_or("Overflow adding minimum interest")
+		} else {
+			Ok(interest)
+		}
+	}
+
+	// The flat late fee, plus the late fee rate on everything outstanding
+	fn late_fee_for(debt: &DebtOf<T>) -> result::Result<BalanceOf<T>, &'static str> {
+		let outstanding = Self::owed(debt)?;
+		let share = simple_interest(outstanding.as_(), Self::late_fee_rate(), 1)?;
+		Self::late_fee().checked_add(&<BalanceOf<T> as As<u64>>::sa(share))
+			.ok_or("Overflow calculating late fee")
+	}
+
+	// Splits the principal into equal installments, due evenly over the term.
+	// The last installment takes the rounding remainder. Interest isn't scheduled: every
+	// repayment pays the interest accrued so far first, and what's left of it is due at term end
+	fn amortize(principal: BalanceOf<T>, term_length: T::Moment, installments: u32) -> Vec<Installment<BalanceOf<T>, T::Moment>> {
+		if installments < 2 {
+			return Vec::new();
+		}
+
+		let n = installments as u64;
+		let amount = principal / <BalanceOf<T> as As<u64>>::sa(n);
+		let term: u64 = term_length.as_();
+
+		(1..=n).map(|k| Installment {
+			due: T::Moment::sa((term as u128 * k as u128 / n as u128) as u64),
+			amount: if k == n { principal - amount * <BalanceOf<T> as As<u64>>::sa(n - 1) } else { amount },
+			paid: Zero::zero(),
+		}).collect()
+	}
+
+	// Moves a debt onto agreed terms, and respreads the installments it hasn't paid off
+	// over the rest of the new term
+	fn apply_terms(debt_id: T::Hash, debt: &mut DebtOf<T>, proposal: TermsProposal<T::Moment>) {
+		<Proposals<T>>::remove(debt_id);
+		debt.term_length = proposal.term_length;
+		debt.interest_rate = proposal.interest_rate;
+
+		if <Schedules<T>>::exists(debt_id) {
+			let mut schedule = Self::get_schedule(debt_id);
+			let installments = schedule.len();
+			schedule.retain(|i| i.paid >= i.amount);
+
+			let start = schedule.last().map(|i| i.due.clone()).unwrap_or_else(Zero::zero);
+			let unpaid = (installments - schedule.len()) as u32;
+			let rest = Self::amortize(debt.principal, debt.term_length.clone() - start.clone(), unpaid);
+			if rest.is_empty() {
+				schedule.push(Installment { due: debt.term_length.clone(), amount: debt.principal, paid: Zero::zero() });
+			} else {
+				schedule.extend(rest.into_iter().map(|i| Installment { due: start.clone() + i.due, ..i }));
+			}
+			<Schedules<T>>::insert(debt_id, schedule);
+		}
+
+		<Debts<T>>::insert(debt_id, debt.clone());
+	}
+
+	// Applies a principal payment to the earliest installments that aren't paid off
+	fn pay_installments(debt_id: T::Hash, payment: BalanceOf<T>) {
+		if !<Schedules<T>>::exists(debt_id) {
+			return;
+		}
+
+		let mut remaining = payment;
+		let mut schedule = Self::get_schedule(debt_id);
+		for installment in schedule.iter_mut() {
+			if remaining.is_zero() {
+				break;
+			}
+			let due = installment.amount - installment.paid;
+			let paid = cmp::min(due, remaining);
+			installment.paid = installment.paid + paid;
+			remaining = remaining - paid;
+		}
+		<Schedules<T>>::insert(debt_id, schedule);
+	}
+}
+
+// Debts are the reasons tokens get escrowed for. Only the requestor can add collateral,
+// and only while the request is open
+impl<T: Trait> erc721::EscrowAuthority<T::AccountId, T::Hash> for Module<T> {
+	fn can_escrow(who: &T::AccountId, reason: &T::Hash) -> bool {
+		if !<Debts<T>>::exists(reason) {
+			return false;
+		}
+		let debt = Self::get_debt(reason);
+		debt.requestor == *who && debt.status == DebtStatus::Requested
+	}
+}
+
+// Transferring a debt's note sells the receivable: its new holder is paid on repay,
+// and is the one who can seize
+impl<T: Trait> erc721::OnTransfer<T::AccountId, T::Hash> for Module<T> {
+	fn on_transfer(token_id: &T::Hash, to: &T::AccountId) {
+		if !<Debts<T>>::exists(token_id) {
+			return;
+		}
+		let mut debt = Self::get_debt(token_id);
+		if debt.status == DebtStatus::Active || debt.status == DebtStatus::Defaulted {
+			Self::change_creditor(*token_id, &mut debt, to.clone());
+			<Debts<T>>::insert(token_id, debt);
+			Self::deposit_event(RawEvent::CreditorChanged(*token_id, to.clone()));
+		}
+	}
+}
+
+// Liquidated debts close once their collateral's auction settles
+impl<T: Trait> auction::OnSettle<T::Hash, BalanceOf<T>> for Module<T> {
+	fn on_settle(auction_id: &T::Hash, proceeds: Option<BalanceOf<T>>) {
+		if !<Debts<T>>::exists(auction_id) {
+			return;
+		}
+		let mut debt = Self::get_debt(auction_id);
+		if Self::set_status(*auction_id, &mut debt, DebtStatus::Liquidated).is_ok() {
+			Self::close(*auction_id, &debt);
+			<Debts<T>>::insert(auction_id, debt);
+			Self::deposit_event(RawEvent::DebtLiquidated(*auction_id, proceeds.unwrap_or_else(Zero::zero)));
+		}
+	}
 }
 
 decl_event!(
 	pub enum Event<T> where 
 		<T as system::Trait>::AccountId,
 		<T as system::Trait>::Hash,
+		Balance = BalanceOf<T>,
+		Moment = <T as timestamp::Trait>::Moment,
 	{
 		DebtBorrowed(AccountId, Hash),
+		BalanceCollateralized(AccountId, Hash, Balance),
+		CollateralAdded(AccountId, Hash),
+		SubstitutionApproved(AccountId, Hash),
+		CollateralSubstituted(AccountId, Hash),
 		DebtFulfilled(AccountId, Hash), 
-		DebtRepaid(AccountId, Hash),
+		DebtCancelled(AccountId, Hash),
+		// late fee, interest, principal and prepayment penalty paid
+		DebtRepaid(AccountId, Hash, Balance, Balance, Balance, Balance),
 		DebtSeized(AccountId, Hash),
+		DebtLiquidating(AccountId, Hash),
+		// what the collateral sold for, or what the reserve paid if nothing was auctioned.
+		// Zero if it went to the creditor unsold
+		DebtLiquidated(Hash, Balance),
+		// who made the call, and the borrower's deadline to meet it
+		MarginCalled(AccountId, Hash, Moment),
+		MarginCallMet(Hash),
+		DebtStatusChanged(Hash, DebtStatus),
+		LateFeeCharged(Hash, Balance),
+		CreditorChanged(Hash, AccountId),
+		ExtensionProposed(AccountId, Hash),
+		ExtensionAccepted(AccountId, Hash),
+		DebtRefinanced(AccountId, Hash),
+		LoanOffered(AccountId, Hash),
+		OfferCancelled(AccountId, Hash),
+		OfferAccepted(AccountId, Hash, Hash),
 	}
 );
@@ -1,13 +1,15 @@
 #[cfg(test)] // allows us to compile code, based on the "test" flag.
 
 use super::*;
+use super::interest::compound_interest;
+use super::super::oracle;
 use support::{impl_outer_origin};
 use runtime_io::with_externalities;
 use primitives::{H256, Blake2Hasher}; //called substrate_primitives as primitives
 use support::{assert_ok, assert_noop};
 use runtime_primitives::{
     BuildStorage,
-    traits::{IdentityLookup, BlakeTwo256}, // Test wrapper for this specific type/ looks up the identity; returns Result
+    traits::{IdentityLookup, BlakeTwo256, OnInitialize}, // Test wrapper for this specific type/ looks up the identity; returns Result
     testing::{Digest, DigestItem, Header}
 };
 
@@ -55,13 +57,32 @@ impl timestamp::Trait for Test {
 
 impl erc721::Trait for Test{
 	type Event = ();
+	type EscrowAuthority = Module<Test>;
+	type OnTransfer = Module<Test>;
 	// type Currency = Balance;
 }
 
+impl auction::Trait for Test {
+	type Event = ();
+	type Currency = balances::Module<Test>;
+	type Collateral = erc721::Module<Test>;
+	type OnSettle = Module<Test>;
+}
+
+impl oracle::Trait for Test {
+	type Event = ();
+	type Currency = balances::Module<Test>;
+}
+
 // this module, implements the traits.
 impl Trait for Test {
 	type Event = ();
 	type Currency = balances::Module<Test>;
+	type InterestModel = PeriodicInterest;
+	type Collateral = erc721::Module<Test>;
+	type Notes = erc721::Module<Test>;
+	type Liquidator = auction::Module<Test>;
+	type Appraiser = oracle::Module<Test>;
 	// any custom traits from this module?
 }
 
@@ -70,6 +91,9 @@ type Debt = Module<Test>;
 type Balance = balances::Module<Test>;
 type Timestamp = timestamp::Module<Test>;
 type ERC = erc721::Module<Test>;
+type Auction = auction::Module<Test>;
+type Oracle = oracle::Module<Test>;
+type System = system::Module<Test>;
 
 // This function basically just builds a genesis storage key/value store according to
 // our desired mockup.
@@ -87,6 +111,10 @@ fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
 	t.extend(timestamp::GenesisConfig::<Test>{
 		minimum_period: 5,
 	}.build_storage().unwrap().0);
+	t.extend(oracle::GenesisConfig::<Test>{
+		feeders: vec![2],
+		max_age: 10,
+	}.build_storage().unwrap().0);
 	t.into() // what does this do?
 }
 
@@ -95,10 +123,10 @@ fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
 fn should_create_debt_request() {
 	with_externalities(&mut new_test_ext(), || {
 		//       uses the Alias
-		assert_ok!(Debt::borrow(Origin::signed(0), 0, 1, 100, 1, 1, 2));
+		assert_ok!(Debt::borrow(Origin::signed(0), 0, 1, 100, 1, 1, 2, InterestType::Simple, 1));
 
 		// Timestamp hasn't incremented, so hash should stay the time
-		assert_noop!(Debt::borrow( Origin::signed(0), 0, 1, 100, 1, 1, 2),
+		assert_noop!(Debt::borrow( Origin::signed(0), 0, 1, 100, 1, 1, 2, InterestType::Simple, 1),
 		"Error: Debt request already exists");
 	});
 }
@@ -111,7 +139,7 @@ fn should_fulfill_request() {
     let token_id = ERC::token_by_index(0);
 
 		//       uses the aliasing														5%
-		assert_ok!(Debt::borrow(Origin::signed(0), 0, 1, 100, 500, 1, 3));
+		assert_ok!(Debt::borrow(Origin::signed(0), 0, 1, 100, 500, 1, 3, InterestType::Simple, 1));
 		let debt_id = Debt::get_debt_id(0);
 
 		// Debt isn't collateralized yet
@@ -135,7 +163,7 @@ fn can_repay() {
     	// SETUP... is there a way to refactor this
     	ERC::create_token(Origin::signed(1));
     	let token_id = ERC::token_by_index(0);
-			Debt::borrow(Origin::signed(1), 1, 1, 100, 5, 1, 3);
+			Debt::borrow(Origin::signed(1), 1, 1, 100, 5, 1, 3, InterestType::Simple, 1);
 			let debt_id = Debt::get_debt_id(0);
 			ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
 			Debt::fulfill(Origin::signed(2), debt_id).is_ok();
@@ -150,7 +178,7 @@ fn can_repay_interest_first() {
 		with_externalities(&mut new_test_ext(), || {
     	ERC::create_token(Origin::signed(1));
     	let token_id = ERC::token_by_index(0);
-			Debt::borrow(Origin::signed(1), 1, 1, 100, 1000, 1, 3); //100 loan, 10%, 1 period
+			Debt::borrow(Origin::signed(1), 1, 1, 100, 1000, 1, 3, InterestType::Simple, 1); //100 loan, 10%, 1 period
 			let debt_id = Debt::get_debt_id(0);
 			ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
 			Debt::fulfill(Origin::signed(2), debt_id).is_ok();
@@ -179,7 +207,7 @@ fn can_seize() {
 		with_externalities(&mut new_test_ext(), || {
   		ERC::create_token(Origin::signed(1));
     	let token_id = ERC::token_by_index(0);
-			Debt::borrow(Origin::signed(1), 1, 1, 100, 500, 1, 3); //term length is 3, int period is 1
+			Debt::borrow(Origin::signed(1), 1, 1, 100, 500, 1, 3, InterestType::Simple, 1); //term length is 3, int period is 1
 			let debt_id = Debt::get_debt_id(0);
 			ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
 			Debt::fulfill(Origin::signed(2), debt_id).is_ok();	// term start is 0
@@ -199,7 +227,7 @@ fn can_simple_interest() {
     	// 10% interest per period
     	// 10: interest period, every 10 seconds interest is compounded
     	// 500 seconds before collat is seized
-			Debt::borrow(Origin::signed(1), 1, 1, 100, 1000, 10, 500);
+			Debt::borrow(Origin::signed(1), 1, 1, 100, 1000, 10, 500, InterestType::Simple, 1);
 			let debt_id = Debt::get_debt_id(0);
 			ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
 			Debt::fulfill(Origin::signed(2), debt_id).is_ok();	// term start is 0
@@ -225,4 +253,1284 @@ fn can_simple_interest() {
 			assert!(Debt::update_balance(debt_id).is_ok());
 			assert_eq!(Debt::get_debt(debt_id).interest, 50);
   	});
-}
\ No newline at end of file
+}
+
+#[test]
+fn can_compound_interest() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+
+		// 10% interest per period, compounded every 10 seconds
+		Debt::borrow(Origin::signed(1), 1, 1, 100, 1000, 10, 500, InterestType::Compound, 1);
+		let debt_id = Debt::get_debt_id(0);
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+		Debt::fulfill(Origin::signed(2), debt_id).is_ok();
+
+		// balance should be 110, interest should be 10
+		Timestamp::set_timestamp(10);
+		assert!(Debt::update_balance(debt_id).is_ok());
+		assert_eq!(Debt::get_debt(debt_id).interest, 10);
+
+		// balance should be 121, interest is charged on the last period's interest
+		Timestamp::set_timestamp(21);
+		assert!(Debt::update_balance(debt_id).is_ok());
+		assert_eq!(Debt::get_debt(debt_id).interest, 21);
+
+		// several periods at once: 121 * 1.1^3 = 161.05
+		Timestamp::set_timestamp(50);
+		assert!(Debt::update_balance(debt_id).is_ok());
+		assert_eq!(Debt::get_debt(debt_id).interest, 61);
+	});
+}
+
+#[test]
+fn compound_interest_is_overflow_safe() {
+	assert_eq!(compound_interest(100, 1000, 3), Ok(33));
+	assert_eq!(compound_interest(100, 0, 3), Ok(0));
+	assert!(compound_interest(u64::max_value(), 1000, 1).is_err());
+	assert!(compound_interest(100, 10000, 100).is_err());
+}
+
+#[test]
+fn can_quote_interest() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		Debt::borrow(Origin::signed(1), 1, 1, 100, 1000, 10, 500, InterestType::Compound, 1);
+		let debt_id = Debt::get_debt_id(0);
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+
+		// unfulfilled debts don't accrue interest
+		assert_eq!(<PeriodicInterest as InterestModel<Test>>::quote(&Debt::get_debt(debt_id), 100), Ok(0));
+
+		Debt::fulfill(Origin::signed(2), debt_id).is_ok();
+		let debt = Debt::get_debt(debt_id);
+		assert_eq!(<PeriodicInterest as InterestModel<Test>>::quote(&debt, 21), Ok(21));
+
+		// quoting doesn't touch storage
+		assert_eq!(Debt::get_debt(debt_id).interest, 0);
+		assert_eq!(Debt::get_debt(debt_id).n_periods, 0);
+	});
+}
+
+#[test]
+fn can_repay_installments() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+
+		// 100 over 4 installments of 25, due every second
+		assert_ok!(Debt::borrow(Origin::signed(1), 1, 1, 100, 0, 1, 4, InterestType::Simple, 4));
+		let debt_id = Debt::get_debt_id(0);
+		let schedule = Debt::get_schedule(debt_id);
+		assert_eq!(schedule.len(), 4);
+		assert_eq!(schedule[0], Installment { due: 1, amount: 25, paid: 0 });
+		assert_eq!(schedule[3], Installment { due: 4, amount: 25, paid: 0 });
+
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// payments go to the earliest installments first
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 30));
+		assert_eq!(Debt::get_schedule(debt_id)[0].paid, 25);
+		assert_eq!(Debt::get_schedule(debt_id)[1].paid, 5);
+
+		Timestamp::set_timestamp(2);
+		assert_eq!(Debt::missed_deadline(debt_id, &Debt::get_debt(debt_id), 2), None);
+		assert!(Debt::seize(Origin::signed(2), debt_id).is_err());
+	});
+}
+
+#[test]
+fn installments_split_the_principal() {
+	with_externalities(&mut new_test_ext(), || {
+		// the last installment takes the remainder
+		assert_ok!(Debt::borrow(Origin::signed(1), 1, 1, 10, 0, 1, 9, InterestType::Simple, 3));
+		let amounts: Vec<u64> = Debt::get_schedule(Debt::get_debt_id(0)).iter().map(|i| i.amount).collect();
+		assert_eq!(amounts, vec![3, 3, 4]);
+
+		// none of them can be for nothing
+		assert_noop!(Debt::borrow(Origin::signed(2), 2, 1, 2, 0, 1, 9, InterestType::Simple, 3),
+			"Error: more installments than principal");
+	});
+}
+
+#[test]
+fn can_seize_missed_installment() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+
+		// 100 over 3 installments, due at 3, 6 and 10
+		assert_ok!(Debt::borrow(Origin::signed(1), 1, 1, 100, 0, 1, 10, InterestType::Simple, 3));
+		let debt_id = Debt::get_debt_id(0);
+		assert_eq!(Debt::get_schedule(debt_id)[2], Installment { due: 10, amount: 34, paid: 0 });
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		Timestamp::set_timestamp(3);
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 33));
+
+		// second installment is missed, well before the term ends
+		Timestamp::set_timestamp(7);
+		assert_eq!(Debt::missed_deadline(debt_id, &Debt::get_debt(debt_id), 7), Some(6));
+		assert_ok!(Debt::seize(Origin::signed(2), debt_id));
+		assert_eq!(ERC::balance_of(2), 1);
+	});
+}
+
+#[test]
+fn debt_status_follows_lifecycle() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow(Origin::signed(1), 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Requested);
+
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Active);
+
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 100));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Repaid);
+		assert_eq!(ERC::balance_of(1), 1);
+
+		// nothing left to repay or seize
+		Timestamp::set_timestamp(6);
+		assert_noop!(Debt::repay(Origin::signed(1), debt_id, 1), "This debt has been paid off");
+		assert_noop!(Debt::seize(Origin::signed(2), debt_id), "This debt has been paid off");
+	});
+}
+
+#[test]
+fn cannot_seize_twice() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		Debt::borrow(Origin::signed(1), 1, 1, 100, 500, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		Timestamp::set_timestamp(6);
+		assert_ok!(Debt::seize(Origin::signed(2), debt_id));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Seized);
+		assert_eq!(Debt::seize(Origin::signed(2), debt_id), Err("This debt has already been seized"));
+		assert_eq!(Debt::repay(Origin::signed(1), debt_id, 10), Err("This debt is past due"));
+	});
+}
+
+#[test]
+fn cannot_fulfill_expired_request() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		Debt::borrow(Origin::signed(1), 1, 1, 100, 500, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+
+		Timestamp::set_timestamp(2);
+		assert_noop!(Debt::fulfill(Origin::signed(2), debt_id), "This debt request has expired");
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Requested);
+		assert_eq!(Balance::free_balance(&2), 100);
+	});
+}
+
+#[test]
+fn can_cancel_request() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		Debt::borrow(Origin::signed(1), 1, 1, 100, 500, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+		assert_eq!(ERC::balance_of(1), 0);
+
+		assert_noop!(Debt::cancel(Origin::signed(2), debt_id), "Only the requestor can cancel this debt request");
+		assert_ok!(Debt::cancel(Origin::signed(1), debt_id));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Cancelled);
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+
+		// cancelled requests can't be fulfilled or cancelled again
+		assert!(Debt::fulfill(Origin::signed(2), debt_id).is_err());
+		assert_noop!(Debt::cancel(Origin::signed(1), debt_id), "This debt request is no longer open");
+	});
+}
+
+#[test]
+fn can_reclaim_expired_request() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		Debt::borrow(Origin::signed(1), 1, 1, 100, 500, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+
+		Timestamp::set_timestamp(2);
+		assert_ok!(Debt::cancel(Origin::signed(1), debt_id));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Cancelled);
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+	});
+}
+
+#[test]
+fn cannot_cancel_fulfilled_debt() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		Debt::borrow(Origin::signed(1), 1, 1, 100, 500, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+		ERC::collateralize_token(Origin::signed(1), token_id, debt_id);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		assert_noop!(Debt::cancel(Origin::signed(1), debt_id), "This debt request is no longer open");
+		assert_eq!(ERC::owner_of(token_id), None);
+	});
+}
+
+#[test]
+fn can_borrow_against_token() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 500, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_eq!(ERC::escrowed_tokens(debt_id), vec![token_id]);
+		assert_eq!(ERC::balance_of(1), 0);
+
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_eq!(Balance::free_balance(&1), 200);
+	});
+}
+
+#[test]
+fn borrow_against_is_atomic() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+
+		// someone else's token
+		assert_noop!(Debt::borrow_against(Origin::signed(2), token_id, 2, 1, 100, 500, 1, 3, InterestType::Simple, 1),
+			"You cannot lock this asset");
+		// bad terms
+		assert_noop!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 500, 0, 3, InterestType::Simple, 1),
+			"Error: interest period cannot be zero");
+
+		assert_eq!(Debt::get_total_debts(), 0);
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+	});
+}
+
+#[test]
+fn only_requestor_can_escrow() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		ERC::create_token(Origin::signed(2));
+		let token_id = ERC::token_by_index(0);
+		let other_token_id = ERC::token_by_index(1);
+		Debt::borrow(Origin::signed(1), 1, 1, 100, 500, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+
+		// not a debt, or somebody else's debt
+		assert_noop!(ERC::collateralize_token(Origin::signed(1), token_id, H256::zero()),
+			"You cannot escrow tokens for this reason");
+		assert_noop!(ERC::collateralize_token(Origin::signed(2), other_token_id, debt_id),
+			"You cannot escrow tokens for this reason");
+
+		assert_ok!(ERC::collateralize_token(Origin::signed(1), token_id, debt_id));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// escrow is locked for the life of the debt
+		assert_noop!(ERC::collateralize_token(Origin::signed(1), token_id, debt_id), 
+			"You cannot escrow tokens for this reason");
+		assert!(ERC::transfer_from(Origin::signed(2), 1, 2, token_id).is_err());
+		assert_eq!(ERC::escrowed_tokens(debt_id), vec![token_id]);
+	});
+}
+
+#[test]
+fn can_back_debt_with_many_tokens() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		ERC::create_token(Origin::signed(1));
+		let (first, second) = (ERC::token_by_index(0), ERC::token_by_index(1));
+		Debt::borrow(Origin::signed(1), 1, 1, 100, 500, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(ERC::collateralize_token(Origin::signed(1), first, debt_id));
+		assert_ok!(ERC::collateralize_token(Origin::signed(1), second, debt_id));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// creditor gets the whole bundle
+		Timestamp::set_timestamp(6);
+		assert_ok!(Debt::seize(Origin::signed(2), debt_id));
+		assert_eq!(ERC::balance_of(2), 2);
+		assert!(ERC::escrowed_tokens(debt_id).is_empty());
+	});
+}
+
+#[test]
+fn can_collateralize_balance() {
+	with_externalities(&mut new_test_ext(), || {
+		Debt::borrow(Origin::signed(1), 1, 1, 50, 0, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+
+		assert_noop!(Debt::collateralize_balance(Origin::signed(2), debt_id, 60), "Only the requestor can collateralize this debt");
+		assert!(Debt::collateralize_balance(Origin::signed(1), debt_id, 200).is_err());
+		assert_ok!(Debt::collateralize_balance(Origin::signed(1), debt_id, 60));
+		assert_eq!(Balance::reserved_balance(&1), 60);
+		assert_eq!(Debt::get_collateral_balance(debt_id), 60);
+
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_eq!(Balance::free_balance(&1), 90);
+
+		// full repayment unreserves the collateral
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 50));
+		assert_eq!(Balance::reserved_balance(&1), 0);
+		assert_eq!(Balance::free_balance(&1), 100);
+	});
+}
+
+#[test]
+fn can_seize_balance_and_tokens() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		Debt::borrow(Origin::signed(1), 1, 1, 50, 0, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(ERC::collateralize_token(Origin::signed(1), token_id, debt_id));
+		assert_ok!(Debt::collateralize_balance(Origin::signed(1), debt_id, 30));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// reserve is slashed to the creditor along with the token
+		Timestamp::set_timestamp(6);
+		assert_ok!(Debt::seize(Origin::signed(2), debt_id));
+		assert_eq!(ERC::owner_of(token_id), Some(2));
+		assert_eq!(Balance::reserved_balance(&1), 0);
+		assert_eq!(Balance::free_balance(&1), 120);
+		assert_eq!(Balance::free_balance(&2), 80);
+	});
+}
+
+#[test]
+fn cancel_unreserves_balance() {
+	with_externalities(&mut new_test_ext(), || {
+		Debt::borrow(Origin::signed(1), 1, 1, 50, 0, 1, 3, InterestType::Simple, 1);
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::collateralize_balance(Origin::signed(1), debt_id, 30));
+
+		assert_ok!(Debt::cancel(Origin::signed(1), debt_id));
+		assert_eq!(Balance::reserved_balance(&1), 0);
+		assert_eq!(Balance::free_balance(&1), 100);
+	});
+}
+
+#[test]
+fn can_offer_and_cancel() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_ok!(Debt::offer(Origin::signed(2), 10, 50, 500, 1, 3, InterestType::Simple));
+		let offer_id = Debt::get_offer_id(0);
+		assert_eq!(Debt::get_offer(offer_id).unwrap().principal, 50);
+		assert_eq!(Balance::reserved_balance(&2), 50);
+
+		assert!(Debt::offer(Origin::signed(2), 10, 200, 500, 1, 3, InterestType::Simple).is_err());
+
+		assert_noop!(Debt::cancel_offer(Origin::signed(1), offer_id), "Only the creditor can cancel this loan offer");
+		assert_ok!(Debt::cancel_offer(Origin::signed(2), offer_id));
+		assert_eq!(Debt::get_offer(offer_id), None);
+		assert_eq!(Balance::free_balance(&2), 100);
+	});
+}
+
+#[test]
+fn offers_only_release_their_own_reserve() {
+	with_externalities(&mut new_test_ext(), || {
+		// 30 of account 2's reserve is balance collateral for its own request
+		assert_ok!(Debt::borrow(Origin::signed(2), 2, 10, 20, 500, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::collateralize_balance(Origin::signed(2), debt_id, 30));
+
+		assert_ok!(Debt::offer(Origin::signed(2), 10, 50, 500, 1, 3, InterestType::Simple));
+		let offer_id = Debt::get_offer_id(0);
+		assert_eq!(Debt::get_offer_reserve(offer_id), 50);
+		assert_eq!(Balance::reserved_balance(&2), 80);
+
+		assert_ok!(Debt::cancel_offer(Origin::signed(2), offer_id));
+		assert_eq!(Debt::get_offer_reserve(offer_id), 0);
+		assert_eq!(Balance::reserved_balance(&2), 30);
+		assert_eq!(Debt::get_collateral_balance(debt_id), 30);
+	});
+}
+
+#[test]
+fn can_accept_offer() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::offer(Origin::signed(2), 10, 50, 1000, 1, 3, InterestType::Simple));
+		let offer_id = Debt::get_offer_id(0);
+
+		assert_noop!(Debt::accept_offer(Origin::signed(0), offer_id, token_id), "You cannot lock this asset");
+		assert_ok!(Debt::accept_offer(Origin::signed(1), offer_id, token_id));
+
+		// funds are disbursed and the debt is active on the offer's terms
+		let debt_id = Debt::get_debt_id(0);
+		let debt = Debt::get_debt(debt_id);
+		assert_eq!(debt.status, DebtStatus::Active);
+		assert_eq!(debt.creditor, 2);
+		assert_eq!(debt.interest_rate, 1000);
+		assert_eq!(ERC::escrowed_tokens(debt_id), vec![token_id]);
+		assert_eq!(Balance::free_balance(&1), 150);
+		assert_eq!(Balance::reserved_balance(&2), 0);
+		assert_eq!(Balance::free_balance(&2), 50);
+		assert_eq!(Debt::get_offer(offer_id), None);
+
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 50));
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+	});
+}
+
+#[test]
+fn cannot_accept_expired_offer() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::offer(Origin::signed(2), 10, 50, 1000, 1, 3, InterestType::Simple));
+		let offer_id = Debt::get_offer_id(0);
+
+		Timestamp::set_timestamp(11);
+		assert_noop!(Debt::accept_offer(Origin::signed(1), offer_id, token_id), "This loan offer has expired");
+
+		// creditor can still get their funds back
+		assert_ok!(Debt::cancel_offer(Origin::signed(2), offer_id));
+		assert_eq!(Balance::reserved_balance(&2), 0);
+	});
+}
+
+#[test]
+fn failed_offer_acceptance_writes_nothing() {
+	with_externalities(&mut new_test_ext(), || {
+		// account 5 holds a token but no balance, so it can't be paid
+		ERC::create_token(Origin::signed(5));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::offer(Origin::signed(2), 10, 50, 1000, 1, 3, InterestType::Simple));
+		let offer_id = Debt::get_offer_id(0);
+
+		assert_noop!(Debt::accept_offer(Origin::signed(5), offer_id, token_id), "The beneficiary account does not exist");
+
+		// a request paying out to an account that doesn't exist can't take the offer either
+		ERC::create_token(Origin::signed(1));
+		let other_token = ERC::token_by_index(1);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), other_token, 9, 10, 50, 1000, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_eq!(Debt::fulfill_from_offer(debt_id, offer_id), Err("The beneficiary account does not exist"));
+
+		assert!(Debt::get_offer(offer_id).is_some());
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Requested);
+		assert_eq!(ERC::owner_of(token_id), Some(5));
+		assert_eq!(Balance::reserved_balance(&2), 50);
+	});
+}
+
+#[test]
+fn note_holder_is_the_creditor() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// the note has the debt's id
+		assert_eq!(ERC::owner_of(debt_id), Some(2));
+
+		// selling the note sells the receivable
+		assert_ok!(ERC::transfer_from(Origin::signed(2), 2, 0, debt_id));
+		assert_eq!(Debt::get_debt(debt_id).creditor, 0);
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 40));
+		assert_eq!(Balance::free_balance(&0), 140);
+		assert_eq!(Balance::free_balance(&2), 0);
+
+		Timestamp::set_timestamp(6);
+		assert_noop!(Debt::seize(Origin::signed(2), debt_id), "Only the creditor can seize this debt");
+		assert_ok!(Debt::seize(Origin::signed(0), debt_id));
+		assert_eq!(ERC::owner_of(token_id), Some(0));
+		// closed debts have no note
+		assert_eq!(ERC::owner_of(debt_id), None);
+	});
+}
+
+#[test]
+fn note_is_burned_on_repay() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_eq!(ERC::total_supply(), 2);
+
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 100));
+		assert_eq!(ERC::owner_of(debt_id), None);
+		assert_eq!(ERC::total_supply(), 1);
+		assert_eq!(ERC::balance_of(2), 0);
+	});
+}
+
+#[test]
+fn late_repayment_within_grace_period_pays_a_fee() {
+	with_externalities(&mut new_test_ext(), || {
+		<GracePeriod<Test>>::put(3);
+		<LateFee<Test>>::put(5);
+		<LateFeeRate<Test>>::put(1000);
+
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// past the term, but within the grace period
+		Timestamp::set_timestamp(5);
+		assert_eq!(Debt::seize(Origin::signed(2), debt_id), Err("This debt has not defaulted yet"));
+		// 5 flat, plus 10% of the 100 outstanding
+		assert_eq!(Debt::get_debt(debt_id).late_fee, 15);
+		assert!(Debt::get_debt(debt_id).late);
+
+		// the fee is only charged once per missed deadline
+		assert_ok!(Debt::update_balance(debt_id));
+		assert_eq!(Debt::get_debt(debt_id).late_fee, 15);
+
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 115));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Repaid);
+		assert_eq!(Balance::free_balance(&2), 115);
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+	});
+}
+
+#[test]
+fn can_seize_once_grace_period_ends() {
+	with_externalities(&mut new_test_ext(), || {
+		<GracePeriod<Test>>::put(3);
+
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// debts keep the grace period they were requested with
+		<GracePeriod<Test>>::put(0);
+
+		Timestamp::set_timestamp(6);
+		assert_eq!(Debt::missed_deadline(debt_id, &Debt::get_debt(debt_id), 6), Some(3));
+		assert_eq!(Debt::seize(Origin::signed(2), debt_id), Err("This debt has not defaulted yet"));
+
+		Timestamp::set_timestamp(7);
+		assert_eq!(Debt::repay(Origin::signed(1), debt_id, 10), Err("This debt is past due"));
+		assert_ok!(Debt::seize(Origin::signed(2), debt_id));
+		assert_eq!(ERC::owner_of(token_id), Some(2));
+	});
+}
+
+#[test]
+fn creditor_can_extend_the_term() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_noop!(Debt::propose_extension(Origin::signed(1), debt_id, 10, 100), "Only active debts can get new terms");
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		assert_noop!(Debt::propose_extension(Origin::signed(2), debt_id, 10, 100), "Only the requestor can propose new terms");
+		assert_noop!(Debt::propose_extension(Origin::signed(1), debt_id, 2, 100), "An extension cannot shorten the term");
+		assert_ok!(Debt::propose_extension(Origin::signed(1), debt_id, 10, 100));
+		assert_noop!(Debt::accept_extension(Origin::signed(1), debt_id), "Only the creditor can accept new terms");
+		assert_ok!(Debt::accept_extension(Origin::signed(2), debt_id));
+		assert_eq!(Debt::get_proposal(debt_id), None);
+
+		// the old term has ended, the new one hasn't
+		Timestamp::set_timestamp(6);
+		assert_eq!(Debt::seize(Origin::signed(2), debt_id), Err("This debt has not defaulted yet"));
+		// 1% a period at the new rate
+		assert_eq!(Debt::get_debt(debt_id).interest, 6);
+	});
+}
+
+#[test]
+fn extension_respreads_unpaid_installments() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 4, InterestType::Simple, 2));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		Timestamp::set_timestamp(1);
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 50));
+		assert_ok!(Debt::propose_extension(Origin::signed(1), debt_id, 8, 0));
+		assert_ok!(Debt::accept_extension(Origin::signed(2), debt_id));
+
+		assert_eq!(Debt::get_schedule(debt_id), vec![
+			Installment { due: 2, amount: 50, paid: 50 },
+			Installment { due: 8, amount: 50, paid: 0 },
+		]);
+	});
+}
+
+#[test]
+fn new_creditor_can_refinance() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		assert_noop!(Debt::refinance(Origin::signed(0), debt_id), "There are no proposed terms for this debt");
+		assert_ok!(Debt::propose_extension(Origin::signed(1), debt_id, 10, 50));
+		assert_noop!(Debt::refinance(Origin::signed(2), debt_id), "The creditor can accept the extension instead");
+
+		// 0 pays off 2 in full and takes over the debt and its note
+		assert_ok!(Debt::refinance(Origin::signed(0), debt_id));
+		assert_eq!(Balance::free_balance(&0), 0);
+		assert_eq!(Balance::free_balance(&2), 100);
+		assert_eq!(Debt::get_debt(debt_id).creditor, 0);
+		assert_eq!(Debt::get_debt(debt_id).interest_rate, 50);
+		assert_eq!(ERC::owner_of(debt_id), Some(0));
+
+		// the collateral never left escrow
+		assert_eq!(ERC::escrow_of(token_id), Some(debt_id));
+
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 100));
+		assert_eq!(Balance::free_balance(&0), 100);
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+	});
+}
+
+#[test]
+fn refinancing_is_a_prepayment() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 50, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		// 10 minimum interest, 2% penalty, no prepaying before 2
+		assert_ok!(Debt::set_prepayment_terms(Origin::signed(1), debt_id, 10, 200, 2));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_ok!(Debt::propose_extension(Origin::signed(1), debt_id, 10, 50));
+
+		assert_eq!(Debt::refinance(Origin::signed(0), debt_id), Err("This debt cannot be prepaid yet"));
+
+		// 50 principal, 10 minimum interest and a penalty of 1
+		Timestamp::set_timestamp(2);
+		assert_ok!(Debt::refinance(Origin::signed(0), debt_id));
+		assert_eq!(Balance::free_balance(&0), 39);
+		assert_eq!(Balance::free_balance(&2), 111);
+		assert_eq!(Debt::get_debt(debt_id).principal, 61);
+	});
+}
+
+#[test]
+fn refinancing_clears_late_state() {
+	with_externalities(&mut new_test_ext(), || {
+		<GracePeriod<Test>>::put(5);
+		<LateFee<Test>>::put(3);
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		// 25 due at 2 and 25 at 4
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 50, 0, 1, 4, InterestType::Simple, 2));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_ok!(Debt::propose_extension(Origin::signed(1), debt_id, 10, 50));
+
+		// the first installment is missed
+		Timestamp::set_timestamp(3);
+		assert_eq!(Debt::refinance(Origin::signed(1), debt_id), Err("The borrower cannot refinance their own debt"));
+		assert!(Debt::get_debt(debt_id).late);
+
+		// the late fee is paid off to the old creditor, and the new one starts clean
+		assert_ok!(Debt::refinance(Origin::signed(0), debt_id));
+		assert_eq!(Balance::free_balance(&0), 47);
+		let debt = Debt::get_debt(debt_id);
+		assert_eq!((debt.principal, debt.late_fee, debt.late), (53, 0, false));
+	});
+}
+
+#[test]
+fn cannot_prepay_during_lockout() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_noop!(Debt::set_prepayment_terms(Origin::signed(2), debt_id, 0, 0, 2), "Only the requestor can set prepayment terms");
+		assert_noop!(Debt::set_prepayment_terms(Origin::signed(1), debt_id, 0, 0, 4), "The lockout cannot be longer than the term");
+		assert_ok!(Debt::set_prepayment_terms(Origin::signed(1), debt_id, 0, 0, 2));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_noop!(Debt::set_prepayment_terms(Origin::signed(1), debt_id, 0, 0, 0), "This debt request is no longer open");
+
+		Timestamp::set_timestamp(1);
+		assert_noop!(Debt::repay(Origin::signed(1), debt_id, 100), "This debt cannot be prepaid yet");
+
+		Timestamp::set_timestamp(2);
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 100));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Repaid);
+	});
+}
+
+#[test]
+fn prepaying_charges_penalty_and_minimum_interest() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		// at least 10 interest, and 1% on principal repaid early
+		assert_ok!(Debt::set_prepayment_terms(Origin::signed(1), debt_id, 10, 100, 0));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// 10 interest, 100 principal and 1 penalty, the rest isn't charged
+		Timestamp::set_timestamp(1);
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 200));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Repaid);
+		assert_eq!(Debt::get_debt(debt_id).interest_paid, 10);
+		assert_eq!(Balance::free_balance(&1), 89);
+		assert_eq!(Balance::free_balance(&2), 111);
+	});
+}
+
+#[test]
+fn minimum_interest_is_only_charged_on_payoff() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::set_prepayment_terms(Origin::signed(1), debt_id, 10, 100, 0));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// prepaying part of it charges no interest yet
+		Timestamp::set_timestamp(1);
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 50));
+		let debt = Debt::get_debt(debt_id);
+		assert_eq!((debt.principal, debt.interest, debt.interest_paid), (50, 0, 0));
+
+		// paying off the other 50 early does
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 100));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Repaid);
+		assert_eq!(Debt::get_debt(debt_id).interest_paid, 10);
+		assert_eq!(Balance::free_balance(&2), 110);
+	});
+}
+
+#[test]
+fn installments_due_are_not_prepayments() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 4, InterestType::Simple, 2));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::set_prepayment_terms(Origin::signed(1), debt_id, 0, 100, 4));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// the first installment is due at 2, paying it isn't locked out or penalized
+		Timestamp::set_timestamp(2);
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 50));
+		assert_eq!(Balance::free_balance(&2), 50);
+		assert_noop!(Debt::repay(Origin::signed(1), debt_id, 50), "This debt cannot be prepaid yet");
+	});
+}
+
+#[test]
+fn interest_accrues_every_period_without_calls() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 100, 2, 10, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_eq!(Debt::get_accrual_entry((0, 0)), Some((2, debt_id)));
+
+		// nothing is due before the period ends
+		Timestamp::set_timestamp(1);
+		<Debt as OnInitialize<u64>>::on_initialize(1);
+		assert_eq!(Debt::get_debt(debt_id).interest, 0);
+
+		Timestamp::set_timestamp(5);
+		<Debt as OnInitialize<u64>>::on_initialize(2);
+		assert_eq!(Debt::get_debt(debt_id).interest, 2);
+		assert_eq!(Debt::get_accrual_entry((0, 0)), None);
+		assert_eq!(Debt::get_accrual_entry((0, 1)), Some((6, debt_id)));
+		assert_eq!(Debt::get_accrual_head(0), 1);
+
+		// paid off debts leave the queue
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 102));
+		Timestamp::set_timestamp(6);
+		<Debt as OnInitialize<u64>>::on_initialize(3);
+		assert_eq!(Debt::get_accrual_entry((0, 1)), None);
+		assert_eq!(Debt::get_accrual_tail(0), 0);
+		assert_eq!(Debt::get_accrual_count(), 0);
+	});
+}
+
+#[test]
+fn accrual_cursor_only_reads_due_buckets() {
+	with_externalities(&mut new_test_ext(), || {
+		// nothing queued, the cursor skips straight to now
+		Timestamp::set_timestamp(600);
+		<Debt as OnInitialize<u64>>::on_initialize(1);
+		assert_eq!(Debt::get_accrual_cursor(), 10);
+
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 700, 100, 100, 100, 1000, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_eq!(Debt::get_accrual_entry((11, 0)), Some((700, debt_id)));
+
+		// two periods passed, the next one ends in the minute starting at 900
+		Timestamp::set_timestamp(850);
+		<Debt as OnInitialize<u64>>::on_initialize(2);
+		assert_eq!(Debt::get_debt(debt_id).interest, 2);
+		assert_eq!(Debt::get_accrual_entry((11, 0)), None);
+		assert_eq!(Debt::get_accrual_tail(11), 0);
+		assert_eq!(Debt::get_accrual_entry((15, 0)), Some((900, debt_id)));
+		assert_eq!(Debt::get_accrual_cursor(), 14);
+		assert_eq!(Debt::get_accrual_count(), 1);
+	});
+}
+
+#[test]
+fn quote_does_not_write_and_pays_off() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 50, 300, 1, 10, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::set_prepayment_terms(Origin::signed(1), debt_id, 0, 300, 0));
+		assert_eq!(Debt::quote_outstanding(H256::zero(), 0), Err("This debt does not exist"));
+
+		assert_ok!(Debt::fulfill(Origin::signed(0), debt_id));
+
+		// 5 periods at 3%, and 3% on the 50 principal prepaid
+		assert_eq!(Debt::quote_outstanding(debt_id, 5), Ok((50, 7, 58)));
+		// once the term ends nothing is prepaid
+		assert_eq!(Debt::quote_outstanding(debt_id, 10), Ok((50, 15, 65)));
+		assert_eq!(Debt::get_debt(debt_id).interest, 0);
+
+		Timestamp::set_timestamp(5);
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 58));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Repaid);
+	});
+}
+
+#[test]
+fn quote_includes_late_fees_and_defaults() {
+	with_externalities(&mut new_test_ext(), || {
+		<GracePeriod<Test>>::put(5);
+		<LateFee<Test>>::put(2);
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 50, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_eq!(Debt::quote_outstanding(debt_id, 0), Err("This debt was never fulfilled"));
+		assert_ok!(Debt::fulfill(Origin::signed(0), debt_id));
+
+		// past the term, within the grace period the late fee is due too
+		assert_eq!(Debt::quote_outstanding(debt_id, 4), Ok((50, 0, 52)));
+		assert_eq!(Debt::quote_outstanding(debt_id, 9), Err("This debt would have defaulted by then"));
+
+		// charged only once
+		Timestamp::set_timestamp(4);
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 1));
+		assert_eq!(Debt::quote_outstanding(debt_id, 5), Ok((50, 0, 51)));
+		assert_ok!(Debt::repay(Origin::signed(1), debt_id, 51));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Repaid);
+	});
+}
+
+#[test]
+fn can_find_debts_by_party_and_status() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_ok!(Debt::borrow(Origin::signed(0), 0, 1, 50, 0, 1, 3, InterestType::Simple, 1));
+		Timestamp::set_timestamp(1);
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 50, 0, 1, 3, InterestType::Simple, 1));
+		let (first, second) = (Debt::get_debt_id(0), Debt::get_debt_id(1));
+		assert_ok!(Debt::fulfill(Origin::signed(2), second));
+
+		assert_eq!(Debt::debts_where(|debt| debt.requestor == 1), vec![second]);
+		assert_eq!(Debt::debts_where(|debt| debt.status == DebtStatus::Requested), vec![first]);
+		assert_eq!(ERC::tokens_of(&2), vec![second]);
+	});
+}
+
+#[test]
+fn accounts_index_their_open_debts() {
+	with_externalities(&mut new_test_ext(), || {
+		assert_ok!(Debt::borrow(Origin::signed(1), 0, 1, 50, 0, 1, 3, InterestType::Simple, 1));
+		Timestamp::set_timestamp(1);
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 50, 0, 1, 3, InterestType::Simple, 1));
+		let (first, second) = (Debt::get_debt_id(0), Debt::get_debt_id(1));
+
+		assert_eq!(Debt::debts_of(&1, DebtRole::Requestor), vec![first, second]);
+		assert_eq!(Debt::debts_of(&0, DebtRole::Beneficiary), vec![first]);
+		assert_eq!(Debt::debts_of(&1, DebtRole::Beneficiary), vec![second]);
+
+		assert_ok!(Debt::fulfill(Origin::signed(2), second));
+		assert_eq!(Debt::debts_of(&2, DebtRole::Creditor), vec![second]);
+
+		// selling the note moves the debt to the buyer's index
+		assert_ok!(ERC::transfer_from(Origin::signed(2), 2, 0, second));
+		assert!(Debt::debts_of(&2, DebtRole::Creditor).is_empty());
+		assert_eq!(Debt::debts_of(&0, DebtRole::Creditor), vec![second]);
+
+		// closed debts leave every index, the last one is swapped into their place
+		assert_ok!(Debt::cancel(Origin::signed(1), first));
+		assert_eq!(Debt::debts_of(&1, DebtRole::Requestor), vec![second]);
+		assert!(Debt::debts_of(&0, DebtRole::Beneficiary).is_empty());
+
+		assert_ok!(Debt::repay(Origin::signed(1), second, 50));
+		assert!(Debt::debts_of(&1, DebtRole::Requestor).is_empty());
+		assert!(Debt::debts_of(&0, DebtRole::Creditor).is_empty());
+	});
+}
+
+#[test]
+fn liquidation_pays_creditor_and_returns_surplus() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 50, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+		assert_noop!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::English), "This debt has not defaulted yet");
+
+		Timestamp::set_timestamp(4);
+		assert_eq!(Debt::liquidate(Origin::signed(1), debt_id, AuctionStyle::English), Err("Only the creditor can liquidate this debt"));
+		assert_ok!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::English));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Liquidating);
+		assert_noop!(Debt::seize(Origin::signed(2), debt_id), "This debt is being liquidated");
+		assert_noop!(Debt::repay(Origin::signed(1), debt_id, 50), "This debt is past due");
+
+		// the collateral stays in escrow for the debt while it's auctioned
+		assert_ok!(Auction::bid(Origin::signed(0), debt_id, 80));
+		assert_eq!(ERC::escrow_of(token_id), Some(debt_id));
+
+		System::set_block_number(1);
+		assert_ok!(Auction::settle(Origin::signed(0), debt_id));
+		assert_eq!(ERC::owner_of(token_id), Some(0));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Liquidated);
+		assert!(Debt::debts_of(&2, DebtRole::Creditor).is_empty());
+
+		// the 50 owed goes to the creditor, the other 30 to the borrower
+		assert_eq!(Balance::free_balance(&0), 20);
+		assert_eq!(Balance::free_balance(&1), 180);
+		assert_eq!(Balance::free_balance(&2), 100);
+	});
+}
+
+#[test]
+fn unsold_collateral_goes_to_the_creditor() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 50, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::collateralize_balance(Origin::signed(1), debt_id, 20));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		Timestamp::set_timestamp(4);
+		assert_ok!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::English));
+		// reserved balance goes towards what's owed right away
+		assert_eq!(Balance::free_balance(&2), 70);
+		assert_eq!(Balance::reserved_balance(&1), 0);
+
+		System::set_block_number(1);
+		assert_ok!(Auction::settle(Origin::signed(2), debt_id));
+		assert_eq!(ERC::owner_of(token_id), Some(2));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Liquidated);
+	});
+}
+
+#[test]
+fn liquidation_needs_a_creditor_to_pay() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 100, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::collateralize_balance(Origin::signed(1), debt_id, 20));
+		// lending all of it leaves the creditor's account empty
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		Timestamp::set_timestamp(4);
+		assert_eq!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::English), Err("The creditor account does not exist"));
+		assert_eq!(Auction::get_auction(debt_id), None);
+		assert_eq!(Debt::get_collateral_balance(debt_id), 20);
+		assert_eq!(Balance::reserved_balance(&1), 20);
+	});
+}
+
+#[test]
+fn covered_liquidations_return_the_tokens() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 1, 1, 50, 0, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::collateralize_balance(Origin::signed(1), debt_id, 60));
+		assert_ok!(Debt::fulfill(Origin::signed(2), debt_id));
+
+		// the reserve pays the 50 owed, so there's nothing to auction
+		Timestamp::set_timestamp(4);
+		assert_ok!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::Dutch));
+		assert_eq!(Auction::get_auction(debt_id), None);
+		assert_eq!(ERC::owner_of(token_id), Some(1));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Liquidated);
+		assert!(Debt::debts_of(&2, DebtRole::Creditor).is_empty());
+
+		assert_eq!(Balance::free_balance(&1), 100);
+		assert_eq!(Balance::reserved_balance(&1), 0);
+		assert_eq!(Balance::free_balance(&2), 100);
+	});
+}
+
+#[test]
+fn loan_to_value_limits_principal() {
+	with_externalities(&mut new_test_ext(), || {
+		<MaxLoanToValue<Test>>::put(5_000);
+		ERC::create_token(Origin::signed(0));
+		let token_id = ERC::token_by_index(0);
+
+		assert_noop!(Debt::borrow_against(Origin::signed(0), token_id, 0, 100, 100, 500, 1, 3, InterestType::Simple, 1),
+			"The collateral has no current appraisal");
+
+		// 100 is more than half of 150
+		assert_ok!(Oracle::appraise(Origin::signed(2), token_id, 150));
+		assert_noop!(Debt::borrow_against(Origin::signed(0), token_id, 0, 100, 100, 500, 1, 3, InterestType::Simple, 1),
+			"The principal is too high for the collateral's value");
+
+		assert_ok!(Oracle::appraise(Origin::signed(2), token_id, 200));
+		assert_ok!(Debt::borrow_against(Origin::signed(0), token_id, 0, 100, 100, 500, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_eq!(Debt::collateral_value(debt_id), Ok(200));
+
+		assert_ok!(Debt::fulfill(Origin::signed(1), debt_id));
+	});
+}
+
+#[test]
+fn stale_appraisals_cannot_back_loans() {
+	with_externalities(&mut new_test_ext(), || {
+		<MaxLoanToValue<Test>>::put(5_000);
+		ERC::create_token(Origin::signed(0));
+		let token_id = ERC::token_by_index(0);
+
+		assert_ok!(Debt::borrow(Origin::signed(0), 0, 100, 60, 500, 1, 3, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(ERC::collateralize_token(Origin::signed(0), token_id, debt_id));
+		assert_ok!(Oracle::appraise(Origin::signed(2), token_id, 100));
+
+		// Appraisals are good for 10
+		Timestamp::set_timestamp(11);
+		assert_eq!(Debt::fulfill(Origin::signed(1), debt_id), Err("The collateral has no current appraisal"));
+
+		// Reserved balance counts at face value
+		assert_ok!(Oracle::appraise(Origin::signed(2), token_id, 100));
+		assert_eq!(Debt::fulfill(Origin::signed(1), debt_id), Err("The principal is too high for the collateral's value"));
+		assert_ok!(Debt::collateralize_balance(Origin::signed(0), debt_id, 20));
+		assert_eq!(Debt::collateral_value(debt_id), Ok(120));
+		assert_ok!(Debt::fulfill(Origin::signed(1), debt_id));
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Active);
+	});
+}
+
+// Borrows 50 from 1 against a token of 0's appraised at 100, margin called under 80%
+fn borrow_with_margin() -> (H256, H256) {
+	<MaintenanceLoanToValue<Test>>::put(8_000);
+	<MarginCallPeriod<Test>>::put(5);
+	ERC::create_token(Origin::signed(0));
+	let token_id = ERC::token_by_index(0);
+	assert_ok!(Oracle::appraise(Origin::signed(2), token_id, 100));
+
+	assert_ok!(Debt::borrow_against(Origin::signed(0), token_id, 0, 1, 50, 0, 1, 30, InterestType::Simple, 1));
+	let debt_id = Debt::get_debt_id(0);
+	assert_ok!(Debt::fulfill(Origin::signed(1), debt_id));
+	(debt_id, token_id)
+}
+
+#[test]
+fn margin_calls_can_be_met() {
+	with_externalities(&mut new_test_ext(), || {
+		let (debt_id, token_id) = borrow_with_margin();
+		assert_eq!(Debt::loan_to_value(debt_id, &Debt::get_debt(debt_id)), Ok(5_000));
+		assert_eq!(Debt::margin_call(Origin::signed(2), debt_id), Err("This debt is not undercollateralized"));
+
+		// 50 is more than 80% of 60
+		assert_ok!(Oracle::appraise(Origin::signed(2), token_id, 60));
+		assert_ok!(Debt::margin_call(Origin::signed(2), debt_id));
+		assert_eq!(Debt::get_margin_call(debt_id), Some(5));
+		assert_eq!(Debt::margin_call(Origin::signed(2), debt_id), Err("This debt already has a margin call"));
+
+		// Only the borrower can add collateral, and an unappraised token doesn't meet the call
+		ERC::create_token(Origin::signed(0));
+		let other_token = ERC::token_by_index(1);
+		assert_noop!(Debt::add_collateral(Origin::signed(1), debt_id, other_token), "Only the requestor can collateralize this debt");
+		assert_ok!(Debt::add_collateral(Origin::signed(0), debt_id, other_token));
+		assert_eq!(ERC::escrow_of(other_token), Some(debt_id));
+		assert_eq!(Debt::get_margin_call(debt_id), Some(5));
+
+		assert_ok!(Oracle::appraise(Origin::signed(2), other_token, 10));
+		assert_ok!(Debt::collateralize_balance(Origin::signed(0), debt_id, 5));
+		assert_eq!(Debt::get_margin_call(debt_id), None);
+
+		Timestamp::set_timestamp(6);
+		assert_eq!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::English), Err("This debt has not defaulted yet"));
+	});
+}
+
+#[test]
+fn expired_margin_calls_can_be_liquidated_by_anyone() {
+	with_externalities(&mut new_test_ext(), || {
+		let (debt_id, token_id) = borrow_with_margin();
+		assert_ok!(Oracle::appraise(Origin::signed(2), token_id, 60));
+		assert_ok!(Debt::margin_call(Origin::signed(2), debt_id));
+
+		// Not before the deadline
+		assert_eq!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::English), Err("This debt has not defaulted yet"));
+
+		Timestamp::set_timestamp(6);
+		assert_ok!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::English));
+		let debt = Debt::get_debt(debt_id);
+		assert_eq!(debt.status, DebtStatus::Liquidating);
+		assert_eq!(Debt::get_margin_call(debt_id), None);
+		assert_eq!(ERC::escrow_of(token_id), Some(debt_id));
+	});
+}
+
+#[test]
+fn margin_calls_without_tokens_settle_from_the_reserve() {
+	with_externalities(&mut new_test_ext(), || {
+		<MaintenanceLoanToValue<Test>>::put(8_000);
+		<MarginCallPeriod<Test>>::put(5);
+		assert_ok!(Debt::borrow(Origin::signed(0), 0, 1, 50, 0, 1, 30, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::collateralize_balance(Origin::signed(0), debt_id, 40));
+		assert_ok!(Debt::fulfill(Origin::signed(1), debt_id));
+
+		// 50 is more than 80% of the 40 reserved
+		assert_ok!(Debt::margin_call(Origin::signed(2), debt_id));
+		Timestamp::set_timestamp(6);
+		assert_ok!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::English));
+
+		// the creditor gets all 40 of the reserve, and the debt is closed
+		assert_eq!(Auction::get_auction(debt_id), None);
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Liquidated);
+		assert!(Debt::debts_of(&1, DebtRole::Creditor).is_empty());
+		assert_eq!(Balance::free_balance(&1), 90);
+		assert_eq!(Balance::free_balance(&0), 110);
+		assert_eq!(Balance::reserved_balance(&0), 0);
+	});
+}
+
+#[test]
+fn repaying_down_meets_a_margin_call() {
+	with_externalities(&mut new_test_ext(), || {
+		let (debt_id, token_id) = borrow_with_margin();
+		assert_ok!(Oracle::appraise(Origin::signed(2), token_id, 60));
+		assert_ok!(Debt::margin_call(Origin::signed(2), debt_id));
+
+		// 40 is less than 80% of 60
+		assert_ok!(Debt::repay(Origin::signed(0), debt_id, 10));
+		assert_eq!(Debt::get_margin_call(debt_id), None);
+
+		// Once met, it can't be liquidated early
+		Timestamp::set_timestamp(6);
+		assert_eq!(Debt::liquidate(Origin::signed(2), debt_id, AuctionStyle::English), Err("This debt has not defaulted yet"));
+	});
+}
+
+#[test]
+fn creditors_can_approve_collateral_substitution() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(0));
+		ERC::create_token(Origin::signed(0));
+		let (old_token, new_token) = (ERC::token_by_index(0), ERC::token_by_index(1));
+		assert_ok!(Debt::borrow_against(Origin::signed(0), old_token, 0, 1, 50, 0, 1, 30, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(1), debt_id));
+
+		// Neither token is appraised
+		assert_eq!(Debt::substitute_collateral(Origin::signed(0), debt_id, old_token, new_token),
+			Err("The collateral has no current appraisal"));
+		assert_noop!(Debt::approve_substitution(Origin::signed(0), debt_id, old_token, new_token),
+			"Only the creditor can approve a substitution");
+		assert_noop!(Debt::approve_substitution(Origin::signed(1), debt_id, new_token, old_token),
+			"This asset is not collateral for this debt");
+
+		assert_ok!(Debt::approve_substitution(Origin::signed(1), debt_id, old_token, new_token));
+		assert_eq!(Debt::substitute_collateral(Origin::signed(1), debt_id, old_token, new_token),
+			Err("Only the requestor can substitute collateral"));
+		assert_ok!(Debt::substitute_collateral(Origin::signed(0), debt_id, old_token, new_token));
+
+		assert_eq!(ERC::escrow_of(new_token), Some(debt_id));
+		assert_eq!(ERC::owner_of(old_token), Some(0));
+		assert_eq!(Debt::get_substitution_approval(debt_id), None);
+
+		// Repaying returns the new token
+		assert_ok!(Debt::repay(Origin::signed(0), debt_id, 50));
+		assert_eq!(ERC::owner_of(new_token), Some(0));
+	});
+}
+
+#[test]
+fn selling_the_note_drops_substitution_approvals() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(0));
+		ERC::create_token(Origin::signed(0));
+		let (old_token, new_token) = (ERC::token_by_index(0), ERC::token_by_index(1));
+		assert_ok!(Debt::borrow_against(Origin::signed(0), old_token, 0, 1, 50, 0, 1, 30, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(1), debt_id));
+		assert_ok!(Debt::approve_substitution(Origin::signed(1), debt_id, old_token, new_token));
+
+		// the buyer of the note never approved the swap
+		assert_ok!(ERC::transfer_from(Origin::signed(1), 1, 2, debt_id));
+		assert_eq!(Debt::get_substitution_approval(debt_id), None);
+		assert_eq!(Debt::substitute_collateral(Origin::signed(0), debt_id, old_token, new_token),
+			Err("The collateral has no current appraisal"));
+		assert_eq!(ERC::escrow_of(old_token), Some(debt_id));
+	});
+}
+
+#[test]
+fn appraised_collateral_can_be_substituted_for_as_much() {
+	with_externalities(&mut new_test_ext(), || {
+		ERC::create_token(Origin::signed(0));
+		ERC::create_token(Origin::signed(0));
+		let (old_token, new_token) = (ERC::token_by_index(0), ERC::token_by_index(1));
+		assert_ok!(Debt::borrow_against(Origin::signed(0), old_token, 0, 1, 50, 0, 1, 30, InterestType::Simple, 1));
+		let debt_id = Debt::get_debt_id(0);
+		assert_ok!(Debt::fulfill(Origin::signed(1), debt_id));
+
+		assert_ok!(Oracle::appraise(Origin::signed(2), old_token, 100));
+		assert_ok!(Oracle::appraise(Origin::signed(2), new_token, 90));
+		assert_eq!(Debt::substitute_collateral(Origin::signed(0), debt_id, old_token, new_token),
+			Err("The new collateral is worth less than the old one"));
+		assert_eq!(Debt::substitute_collateral(Origin::signed(0), debt_id, new_token, old_token),
+			Err("This asset is not collateral for this debt"));
+
+		assert_ok!(Oracle::appraise(Origin::signed(2), new_token, 100));
+		assert_ok!(Debt::substitute_collateral(Origin::signed(0), debt_id, old_token, new_token));
+		assert_eq!(ERC::escrowed_tokens(debt_id), vec![new_token]);
+		assert_eq!(ERC::owner_of(old_token), Some(0));
+	});
+}
@@ -0,0 +1,33 @@
+//! Runtime APIs, so clients can read module state without sending transactions
+
+use client::decl_runtime_apis;
+use parity_codec::Codec;
+use rstd::prelude::*;
+use super::debt::DebtStatus;
+
+decl_runtime_apis! {
+	/// Read-only queries of debts, and the erc721 tokens securing them
+	pub trait DebtApi<AccountId, Hash, Balance, Moment> where
+		AccountId: Codec,
+		Hash: Codec,
+		Balance: Codec,
+		Moment: Codec,
+	{
+		/// Open debts `who` requested
+		fn debts_by_requestor(who: AccountId) -> Vec<Hash>;
+		/// Open debts paid out to `who`
+		fn debts_by_beneficiary(who: AccountId) -> Vec<Hash>;
+		/// Open debts `who` is currently the creditor of
+		fn debts_by_creditor(who: AccountId) -> Vec<Hash>;
+		/// Debts currently in `status`, in the order they were requested. There's no index by
+		/// status, so this reads every debt ever created and slows down as the chain grows
+		fn debts_by_status(status: DebtStatus) -> Vec<Hash>;
+		/// Tokens escrowed for a debt, and the balance reserved for it
+		fn collateral_of(debt_id: Hash) -> (Vec<Hash>, Balance);
+		/// Tokens `owner` holds, escrowed tokens aren't included
+		fn tokens_of(owner: AccountId) -> Vec<Hash>;
+		/// (principal, interest, total) it takes to pay off a debt at `at`, or now if that's none.
+		/// None if the debt doesn't exist or can't be paid off then
+		fn quote_outstanding(debt_id: Hash, at: Option<Moment>) -> Option<(Balance, Balance, Balance)>;
+	}
+}
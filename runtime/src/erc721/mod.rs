@@ -20,6 +20,7 @@ use system::ensure_signed;
 use parity_codec::Encode; // serialization and deserialization codec for simple marshalling.
 use runtime_primitives::traits::{Hash, Zero};
 use rstd::prelude::*;
+use super::collateral::Collateral;
 
 
 #[cfg(test)] //tells compiler to compile based on "test" flag. i.e. its a test.
@@ -30,6 +31,47 @@ pub trait Trait: balances::Trait {
 
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The module that owns escrow reasons, e.g. the debt module for debt ids
+	type EscrowAuthority: EscrowAuthority<Self::AccountId, Self::Hash>;
+
+	/// Told whenever a token changes hands
+	type OnTransfer: OnTransfer<Self::AccountId, Self::Hash>;
+}
+
+/// Hook for modules that track tokens, e.g. the debt module following who holds a note
+pub trait OnTransfer<AccountId, Hash> {
+    fn on_transfer(token_id: &Hash, to: &AccountId);
+}
+
+impl<AccountId, Hash> OnTransfer<AccountId, Hash> for () {
+    fn on_transfer(_: &Hash, _: &AccountId) {}
+}
+
+/// Lets other modules mint tokens for things they track, e.g. the debt module's notes
+pub trait TokenIssuer<AccountId, Hash> {
+    /// Mints `token_id` to `to`, fails if it already exists
+    fn mint(to: &AccountId, token_id: Hash) -> Result;
+    /// Burns an owned token
+    fn burn(token_id: Hash) -> Result;
+    /// Current owner of a token, none if it doesn't exist or is escrowed
+    fn owner(token_id: Hash) -> Option<AccountId>;
+}
+
+/// Escrow authority: the module whose obligations tokens are escrowed for.
+/// It decides who can escrow tokens for a reason through `collateralize_token`. Modules lock
+/// and release tokens for their own reasons through the `Collateral` trait, there is no
+/// dispatchable to release them.
+pub trait EscrowAuthority<AccountId, Hash> {
+    /// Whether `who` may escrow tokens against `reason`
+    fn can_escrow(who: &AccountId, reason: &Hash) -> bool;
+}
+
+/// Lets anyone escrow tokens for any reason
+impl<AccountId, Hash> EscrowAuthority<AccountId, Hash> for () {
+    fn can_escrow(_: &AccountId, _: &Hash) -> bool {
+        true
+    }
 }
 
 decl_event!(
@@ -61,8 +103,12 @@ decl_storage! {
         OwnedTokensIndex: map T::Hash => u64;
         // Start ERC721 : Enumerable : Storage & Getters //
 
-        // @nczhu: Mapping of reason to token_id collateralized for it
-        Escrow get(get_escrow): map T::Hash => T::Hash;
+        // @nczhu: Enumerable tokens collateralized for a reason
+        EscrowCount get(escrow_count): map T::Hash => u64;
+        Escrow get(escrowed_token_by_index): map (T::Hash, u64) => T::Hash;
+        EscrowIndex: map T::Hash => u64;
+        // Reverse of Escrow: the reason a token is collateralized for
+        TokenEscrow get(escrow_of): map T::Hash => Option<T::Hash>;
         
         // Not a part of the ERC721 specification, but used in random token generation
         Nonce: u64;
@@ -142,6 +188,7 @@ decl_module! {
         pub fn collateralize_token(origin, token_id: T::Hash, reason: T::Hash) {
             // "Locks" token from leaving 
             let sender = ensure_signed(origin)?;
+            ensure!(T::EscrowAuthority::can_escrow(&sender, &reason), "You cannot escrow tokens for this reason");
 
             Self::_collateralize(sender, token_id, reason)?;
 
@@ -150,14 +197,6 @@ decl_module! {
             // TODO: emit some event here
         }
 
-        // Only callable by the system
-        // Gives collateralized token to an account
-        // Can be debtor, or creditor
-        // Only collable by the system
-        pub fn uncollateralize_token(to: T::AccountId, reason: T::Hash) {
-            Self::_uncollateralize(to, reason);
-        }
-
     }
 }
 
@@ -178,37 +217,91 @@ impl<T: Trait> Module<T> {
             None => return Err("Collateralizing causes underflow of token balance"),
         };
 
+        let escrow_count = Self::escrow_count(reason);
+        let new_escrow_count = match escrow_count.checked_add(1) {
+            Some (c) => c,
+            None => return Err("Overflow adding a token to escrow"),
+        };
+
         Self::_remove_token_from_owner_enumeration(sender.clone(), token_id)?;
         Self::_clear_approval(token_id)?;
         <OwnedTokensCount<T>>::insert(&sender, new_balance);
         <TokenOwner<T>>::remove(token_id);
 
         //Add to escrow
-        <Escrow<T>>::insert(reason, token_id);
+        <Escrow<T>>::insert((reason, escrow_count), token_id);
+        <EscrowIndex<T>>::insert(token_id, escrow_count);
+        <EscrowCount<T>>::insert(reason, new_escrow_count);
+        <TokenEscrow<T>>::insert(token_id, reason);
 
         Ok(())
     }
 
+    // Gives all tokens collateralized for a reason to an account, can be debtor or creditor.
+    // Either every token is released or none is.
+    // Not dispatchable: other modules release what they locked through `Collateral`
     fn _uncollateralize(to: T::AccountId, reason: T::Hash) -> Result {
-        ensure!(<Escrow<T>>::exists(reason), "There is no collateral for this id");
-        let token_id = Self::get_escrow(reason);
-        
-        <Escrow<T>>::remove(reason); //delete token "ownership" from escrow
-        // handle all the rewrites
-        <TokenOwner<T>>::insert(token_id, &to);
+        let token_ids = Self::escrowed_tokens(reason);
+        ensure!(!token_ids.is_empty(), "There is no collateral for this id");
+
+        // Check up front, so the release can't fail halfway
         let balance_of = Self::balance_of(&to);
+        ensure!(balance_of.checked_add(token_ids.len() as u64).is_some(), "Overflow adding a new token to account balance");
 
-        let new_balance_of = match balance_of.checked_add(1) {
-            Some(c) => c,
-            None => return Err("Overflow adding a new token to account balance"),
-        };
+        // handle all the rewrites
+        for token_id in token_ids {
+            //delete token "ownership" from escrow
+            <Escrow<T>>::remove((reason, <EscrowIndex<T>>::take(token_id)));
+            <TokenEscrow<T>>::remove(token_id);
+
+            <TokenOwner<T>>::insert(token_id, &to);
+            Self::_add_token_to_owner_enumeration(to.clone(), token_id)?;
+            <OwnedTokensCount<T>>::mutate(&to, |balance| *balance += 1);
+            T::OnTransfer::on_transfer(&token_id, &to);
+        }
+        <EscrowCount<T>>::remove(reason);
 
-        <OwnedTokensCount<T>>::insert(&to, new_balance_of);
-        Self::_add_token_to_owner_enumeration(to, token_id)?;
+        Ok(())
+    }
+
+    // Swaps a token collateralized for a reason for one its owner holds, in place: the new
+    // token takes the old one's escrow slot and the old one takes the new one's owner slot
+    fn _substitute(owner: T::AccountId, old_token: T::Hash, new_token: T::Hash, reason: T::Hash) -> Result {
+        ensure!(Self::escrow_of(old_token) == Some(reason), "This token is not collateralized for this id");
+        ensure!(Self::owner_of(new_token).as_ref() == Some(&owner), "You do not own this token");
+
+        Self::_clear_approval(new_token)?;
+
+        let escrow_index = <EscrowIndex<T>>::take(old_token);
+        <Escrow<T>>::insert((reason, escrow_index), new_token);
+        <EscrowIndex<T>>::insert(new_token, escrow_index);
+        <TokenEscrow<T>>::remove(old_token);
+        <TokenEscrow<T>>::insert(new_token, reason);
+
+        let owned_index = <OwnedTokensIndex<T>>::take(new_token);
+        <OwnedTokens<T>>::insert((owner.clone(), owned_index), old_token);
+        <OwnedTokensIndex<T>>::insert(old_token, owned_index);
+        <TokenOwner<T>>::remove(new_token);
+        <TokenOwner<T>>::insert(old_token, &owner);
+        T::OnTransfer::on_transfer(&old_token, &owner);
 
         Ok(())
     }
 
+    /// All tokens an account owns, escrowed ones excluded
+    pub fn tokens_of(owner: &T::AccountId) -> Vec<T::Hash> {
+        (0..Self::balance_of(owner))
+            .map(|i| Self::token_of_owner_by_index((owner.clone(), i)))
+            .collect()
+    }
+
+    /// All tokens collateralized for a reason
+    pub fn escrowed_tokens(reason: T::Hash) -> Vec<T::Hash> {
+        (0..Self::escrow_count(reason))
+            .map(|i| Self::escrowed_token_by_index((reason, i)))
+            .collect()
+    }
+
     // Start ERC721 : Internal Functions //
     fn _exists(token_id: T::Hash) -> bool {
         return <TokenOwner<T>>::exists(token_id);
@@ -315,6 +408,7 @@ impl<T: Trait> Module<T> {
         <OwnedTokensCount<T>>::insert(&from, new_balance_of_from);
         <OwnedTokensCount<T>>::insert(&to, new_balance_of_to);
         <TokenOwner<T>>::insert(&token_id, &to);
+        T::OnTransfer::on_transfer(&token_id, &to);
 
         Self::deposit_event(RawEvent::Transfer(Some(from), Some(to), token_id));
         
@@ -407,3 +501,45 @@ impl<T: Trait> Module<T> {
     }
     // End ERC721 : Enumerable : Internal Functions //
 }
+
+impl<T: Trait> Collateral<T::AccountId, T::Hash> for Module<T> {
+    type AssetId = T::Hash;
+
+    fn can_lock(owner: &T::AccountId, token_id: &T::Hash) -> bool {
+        Self::owner_of(token_id).as_ref() == Some(owner)
+    }
+
+    fn lock(owner: &T::AccountId, token_id: T::Hash, reason: T::Hash) -> Result {
+        Self::_collateralize(owner.clone(), token_id, reason)
+    }
+
+    fn release(reason: T::Hash, to: &T::AccountId) -> Result {
+        Self::_uncollateralize(to.clone(), reason)
+    }
+
+    fn substitute(owner: &T::AccountId, old_token: T::Hash, new_token: T::Hash, reason: T::Hash) -> Result {
+        Self::_substitute(owner.clone(), old_token, new_token, reason)
+    }
+
+    fn locked_by(reason: T::Hash) -> Vec<T::Hash> {
+        Self::escrowed_tokens(reason)
+    }
+
+    fn is_locked(token_id: T::Hash) -> bool {
+        Self::escrow_of(token_id).is_some()
+    }
+}
+
+impl<T: Trait> TokenIssuer<T::AccountId, T::Hash> for Module<T> {
+    fn mint(to: &T::AccountId, token_id: T::Hash) -> Result {
+        Self::_mint(to.clone(), token_id)
+    }
+
+    fn burn(token_id: T::Hash) -> Result {
+        Self::_burn(token_id)
+    }
+
+    fn owner(token_id: T::Hash) -> Option<T::AccountId> {
+        Self::owner_of(token_id)
+    }
+}
@@ -1,6 +1,7 @@
 #[cfg(test)] // allows us to compile code, based on the "test" flag.
 
 use super::*;
+use super::super::collateral::Collateral;
 use support::{impl_outer_origin};
 use runtime_io::with_externalities;
 use primitives::{H256, Blake2Hasher}; //called substrate_primitives as primitives
@@ -47,6 +48,8 @@ impl balances::Trait for Test {
 // impl the types for this particular trait!
 impl Trait for Test{
     type Event = ();
+    type EscrowAuthority = ();
+    type OnTransfer = ();
     // type Currency = balances::Module<Test>;
 }
 
@@ -80,3 +83,104 @@ fn can_collateralize_token() {
         assert_eq!(ERC::total_supply(), 1); //total supply shouldn't change
     });
 }
+
+#[test]
+fn cannot_move_escrowed_token() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(ERC::create_token(Origin::signed(0)));
+        let token_id = ERC::token_by_index(0);
+        assert_ok!(ERC::collateralize_token(Origin::signed(0), token_id, H256::zero()));
+
+        // neither the previous owner nor anyone else can take it back out
+        assert_noop!(ERC::transfer_from(Origin::signed(0), 0, 1, token_id), "You do not own this token");
+        assert_noop!(ERC::approve(Origin::signed(0), 1, token_id), "No owner for this token");
+        assert_noop!(ERC::collateralize_token(Origin::signed(1), token_id, H256::repeat_byte(1)), "No owner for this token");
+        assert_eq!(ERC::escrowed_tokens(H256::zero()), vec![token_id]);
+    });
+}
+
+#[test]
+fn release_needs_an_escrow() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(ERC::_uncollateralize(1, H256::zero()), "There is no collateral for this id");
+    });
+}
+
+#[test]
+fn can_lock_as_collateral() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(ERC::create_token(Origin::signed(0)));
+        let token_id = ERC::token_by_index(0);
+        let reason = H256::repeat_byte(1);
+
+        assert!(ERC::can_lock(&0, &token_id));
+        assert!(!ERC::can_lock(&1, &token_id));
+        assert_ok!(ERC::lock(&0, token_id, reason));
+        assert!(ERC::is_locked(token_id));
+        assert_eq!(ERC::locked_by(reason), vec![token_id]);
+        assert!(!ERC::can_lock(&0, &token_id));
+
+        assert_ok!(ERC::release(reason, &1));
+        assert!(!ERC::is_locked(token_id));
+        assert!(ERC::locked_by(reason).is_empty());
+        assert_eq!(ERC::owner_of(token_id), Some(1));
+    });
+}
+
+#[test]
+fn can_escrow_many_tokens_for_a_reason() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(ERC::create_token(Origin::signed(0)));
+        assert_ok!(ERC::create_token(Origin::signed(0)));
+        assert_ok!(ERC::create_token(Origin::signed(0)));
+        let (first, second, third) = (ERC::token_by_index(0), ERC::token_by_index(1), ERC::token_by_index(2));
+        let reason = H256::repeat_byte(1);
+
+        assert_ok!(ERC::collateralize_token(Origin::signed(0), first, reason));
+        assert_ok!(ERC::collateralize_token(Origin::signed(0), second, reason));
+        assert_ok!(ERC::collateralize_token(Origin::signed(0), third, H256::repeat_byte(2)));
+
+        // the second token doesn't replace the first one
+        assert_eq!(ERC::escrow_count(reason), 2);
+        assert_eq!(ERC::escrowed_tokens(reason), vec![first, second]);
+        assert_eq!(ERC::balance_of(0), 0);
+
+        // both are released together, other escrows are untouched
+        assert_ok!(ERC::_uncollateralize(1, reason));
+        assert_eq!(ERC::balance_of(1), 2);
+        assert_eq!(ERC::owner_of(first), Some(1));
+        assert_eq!(ERC::owner_of(second), Some(1));
+        assert_eq!(ERC::token_of_owner_by_index((1, 1)), second);
+        assert_eq!(ERC::escrow_count(reason), 0);
+        assert_eq!(ERC::escrowed_tokens(H256::repeat_byte(2)), vec![third]);
+    });
+}
+
+#[test]
+fn can_substitute_escrowed_token() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(ERC::create_token(Origin::signed(0)));
+        assert_ok!(ERC::create_token(Origin::signed(0)));
+        assert_ok!(ERC::create_token(Origin::signed(0)));
+        let (first, second, third) = (ERC::token_by_index(0), ERC::token_by_index(1), ERC::token_by_index(2));
+        let reason = H256::repeat_byte(1);
+        assert_ok!(ERC::lock(&0, first, reason));
+        assert_ok!(ERC::lock(&0, second, reason));
+
+        assert_noop!(ERC::substitute(&0, third, first, reason), "This token is not collateralized for this id");
+        assert_noop!(ERC::substitute(&1, first, third, reason), "You do not own this token");
+
+        // the third token takes the first one's place, the owner's balance doesn't change
+        assert_ok!(ERC::substitute(&0, first, third, reason));
+        assert_eq!(ERC::locked_by(reason), vec![third, second]);
+        assert_eq!(ERC::escrow_of(third), Some(reason));
+        assert_eq!(ERC::escrow_of(first), None);
+        assert_eq!(ERC::owner_of(first), Some(0));
+        assert_eq!(ERC::owner_of(third), None);
+        assert_eq!(ERC::tokens_of(&0), vec![first]);
+        assert_eq!(ERC::balance_of(0), 1);
+
+        assert_ok!(ERC::release(reason, &1));
+        assert_eq!(ERC::tokens_of(&1), vec![third, second]);
+    });
+}
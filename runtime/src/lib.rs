@@ -33,6 +33,8 @@ pub use balances::Call as BalancesCall;
 pub use runtime_primitives::{Permill, Perbill};
 pub use timestamp::BlockPeriod;
 pub use support::{StorageValue, construct_runtime};
+pub use debt::DebtStatus;
+pub use auction::Decay;
 
 /// The type that is used for identifying authorities.
 pub type AuthorityId = <AuthoritySignature as Verify>::Signer;
@@ -55,10 +57,21 @@ pub type BlockNumber = u64;
 /// Index of an account's extrinsic in the chain.
 pub type Nonce = u64;
 
+/// Balance of an account.
+pub type Balance = u128;
+
+/// Time in seconds, as set by the timestamp inherent.
+pub type Moment = u64;
+
 /// Used for the module template in `./template.rs`
 mod template;
+mod collateral;
 mod erc721;
 mod debt;
+mod order_book;
+mod auction;
+mod oracle;
+pub mod debt_api;
 
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
 /// the specifics of the runtime. They can then be made to be agnostic over specific formats
@@ -164,13 +177,13 @@ impl indices::Trait for Runtime {
 
 impl timestamp::Trait for Runtime {
 	/// A timestamp: seconds since the unix epoch.
-	type Moment = u64;
+	type Moment = Moment;
 	type OnTimestampSet = Aura;
 }
 
 impl balances::Trait for Runtime {
 	/// The type for recording an account's balance.
-	type Balance = u128;
+	type Balance = Balance;
 	/// What to do if an account's free balance gets zeroed.
 	type OnFreeBalanceZero = ();
 	/// What to do if a new account is created.
@@ -196,15 +209,35 @@ impl template::Trait for Runtime {
 
 impl erc721::Trait for Runtime {
 	type Event = Event;
+	type EscrowAuthority = Debt;
+	type OnTransfer = Debt;
 }
 
 impl debt::Trait for Runtime {
 	type Event = Event;
 	type Currency = Balances;
+	type InterestModel = debt::PeriodicInterest;
+	type Collateral = Erc721;
+	type Notes = Erc721;
+	type Liquidator = Auction;
+	type Appraiser = Oracle;
+}
+
+impl auction::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Collateral = Erc721;
+	type OnSettle = Debt;
+}
+
+impl oracle::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+}
+
+impl order_book::Trait for Runtime {
+	type Event = Event;
 }
-// impl collateral::Trait for Runtime {
-// 	type Event = Event;
-// }
 
 construct_runtime!(
 	pub enum Runtime with Log(InternalLog: DigestItem<Hash, AuthorityId, AuthoritySignature>) where
@@ -222,8 +255,10 @@ construct_runtime!(
 		// Used for the module template in `./template.rs`
 		TemplateModule: template::{Module, Call, Storage, Event<T>},
 		Erc721: erc721::{Module, Call, Storage, Event<T>},
-		Debt: debt::{Module, Call, Sotrage, Event<T>},
-		// Collateral: collateral::{Module, Call, Storage, Event<T>},
+		Debt: debt::{Module, Call, Storage, Config<T>, Event<T>},
+		OrderBook: order_book::{Module, Call, Storage, Event<T>},
+		Auction: auction::{Module, Call, Storage, Config<T>, Event<T>},
+		Oracle: oracle::{Module, Call, Storage, Config<T>, Event<T>},
 	}
 );
 
@@ -315,4 +350,34 @@ impl_runtime_apis! {
 			Consensus::authorities()
 		}
 	}
+
+	impl debt_api::DebtApi<Block, AccountId, Hash, Balance, Moment> for Runtime {
+		fn debts_by_requestor(who: AccountId) -> Vec<Hash> {
+			Debt::debts_of(&who, debt::DebtRole::Requestor)
+		}
+
+		fn debts_by_beneficiary(who: AccountId) -> Vec<Hash> {
+			Debt::debts_of(&who, debt::DebtRole::Beneficiary)
+		}
+
+		fn debts_by_creditor(who: AccountId) -> Vec<Hash> {
+			Debt::debts_of(&who, debt::DebtRole::Creditor)
+		}
+
+		fn debts_by_status(status: DebtStatus) -> Vec<Hash> {
+			Debt::debts_where(|debt| debt.status == status)
+		}
+
+		fn collateral_of(debt_id: Hash) -> (Vec<Hash>, Balance) {
+			(Erc721::escrowed_tokens(debt_id), Debt::get_collateral_balance(debt_id))
+		}
+
+		fn tokens_of(owner: AccountId) -> Vec<Hash> {
+			Erc721::tokens_of(&owner)
+		}
+
+		fn quote_outstanding(debt_id: Hash, at: Option<Moment>) -> Option<(Balance, Balance, Balance)> {
+			Debt::quote_outstanding(debt_id, at.unwrap_or_else(Timestamp::get)).ok()
+		}
+	}
 }
@@ -0,0 +1,144 @@
+/// Price oracle: whitelisted feeders post appraisals of assets, e.g. erc721 tokens, and
+/// other modules read the latest one as long as it isn't stale.
+/// Feeders can also group tokens into collections and appraise a whole collection at once,
+/// which values its tokens that have no appraisal of their own.
+
+use support::{decl_module, decl_storage, decl_event, StorageValue, StorageMap, ensure};
+use system::{ensure_signed, ensure_root};
+use parity_codec::{Encode, Decode};
+use runtime_primitives::traits::Zero;
+use rstd::prelude::*;
+
+use support::traits::Currency;
+
+pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+#[cfg(test)]
+mod test;
+
+/// The module's configuration trait.
+pub trait Trait: timestamp::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Appraisals are in this currency's balance
+	type Currency: Currency<Self::AccountId>;
+}
+
+/// Values assets, e.g. so the debt module can check loan-to-value ratios
+pub trait Appraiser<Asset, Balance> {
+	/// The asset's current value, none if it has no appraisal or it is stale
+	fn value_of(asset: &Asset) -> Option<Balance>;
+}
+
+impl<Asset, Balance> Appraiser<Asset, Balance> for () {
+	fn value_of(_: &Asset) -> Option<Balance> {
+		None
+	}
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Appraisal<AccountId, Balance, Moment> {
+	pub value: Balance,
+	pub at: Moment,		// when it was posted
+	pub feeder: AccountId,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Oracle {
+		// Accounts allowed to post appraisals
+		Feeders get(feeders) config(): Vec<T::AccountId>;
+		// Latest appraisal of every asset
+		Appraisals get(get_appraisal): map T::Hash => Option<Appraisal<T::AccountId, BalanceOf<T>, T::Moment>>;
+		// Collection of every grouped token
+		CollectionOf get(collection_of): map T::Hash => Option<T::Hash>;
+		// How long appraisals are good for, zero if they don't go stale
+		MaxAge get(max_age) config(): T::Moment;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event<T>() = default;
+
+		pub fn add_feeder(origin, who: T::AccountId) {
+			ensure_root(origin)?;
+			let mut feeders = Self::feeders();
+			ensure!(!feeders.contains(&who), "This account is already a feeder");
+
+			feeders.push(who.clone());
+			<Feeders<T>>::put(feeders);
+
+			Self::deposit_event(RawEvent::FeederAdded(who));
+		}
+
+		pub fn remove_feeder(origin, who: T::AccountId) {
+			ensure_root(origin)?;
+			let mut feeders = Self::feeders();
+			let position = feeders.iter().position(|f| *f == who).ok_or("This account is not a feeder")?;
+
+			feeders.swap_remove(position);
+			<Feeders<T>>::put(feeders);
+
+			Self::deposit_event(RawEvent::FeederRemoved(who));
+		}
+
+		// Feeders post what an asset is worth now, replacing its previous appraisal
+		pub fn appraise(origin, asset: T::Hash, value: BalanceOf<T>) {
+			let feeder = ensure_signed(origin)?;
+			ensure!(Self::feeders().contains(&feeder), "Only feeders can appraise assets");
+
+			let at = <timestamp::Module<T>>::get();
+			<Appraisals<T>>::insert(asset, Appraisal { value, at, feeder: feeder.clone() });
+
+			Self::deposit_event(RawEvent::Appraised(feeder, asset, value));
+		}
+
+		// Feeders can put a token in a collection, so it's worth what the collection is appraised at
+		pub fn set_collection(origin, token_id: T::Hash, collection: Option<T::Hash>) {
+			let feeder = ensure_signed(origin)?;
+			ensure!(Self::feeders().contains(&feeder), "Only feeders can set collections");
+
+			match collection {
+				Some(collection) => <CollectionOf<T>>::insert(token_id, collection),
+				None => <CollectionOf<T>>::remove(token_id),
+			}
+
+			Self::deposit_event(RawEvent::CollectionSet(token_id, collection));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Whether an appraisal posted at `at` is too old to rely on now
+	pub fn is_stale(at: T::Moment) -> bool {
+		let max_age = Self::max_age();
+		!max_age.is_zero() && <timestamp::Module<T>>::get() > at + max_age
+	}
+
+	fn current_value(asset: &T::Hash) -> Option<BalanceOf<T>> {
+		Self::get_appraisal(asset)
+			.filter(|appraisal| !Self::is_stale(appraisal.at.clone()))
+			.map(|appraisal| appraisal.value)
+	}
+}
+
+impl<T: Trait> Appraiser<T::Hash, BalanceOf<T>> for Module<T> {
+	// A token's own appraisal comes first, its collection's is the fallback
+	fn value_of(asset: &T::Hash) -> Option<BalanceOf<T>> {
+		Self::current_value(asset)
+			.or_else(|| Self::collection_of(asset).and_then(|collection| Self::current_value(&collection)))
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		Balance = BalanceOf<T>,
+	{
+		FeederAdded(AccountId),
+		FeederRemoved(AccountId),
+		Appraised(AccountId, Hash, Balance),
+		CollectionSet(Hash, Option<Hash>),
+	}
+);
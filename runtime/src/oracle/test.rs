@@ -0,0 +1,146 @@
+#[cfg(test)]
+
+use super::*;
+use support::{impl_outer_origin, assert_ok, assert_noop};
+use runtime_io::with_externalities;
+use primitives::{H256, Blake2Hasher};
+use runtime_primitives::{
+	BuildStorage,
+	traits::{IdentityLookup, BlakeTwo256},
+	testing::{Digest, DigestItem, Header}
+};
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+impl system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type Digest = Digest;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type Log = DigestItem;
+}
+
+impl balances::Trait for Test {
+	type Balance = u64;
+	type OnFreeBalanceZero = ();
+	type OnNewAccount = ();
+	type Event = ();
+	type TransactionPayment = ();
+	type TransferPayment = ();
+	type DustRemoval = ();
+}
+
+impl timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+}
+
+impl Trait for Test {
+	type Event = ();
+	type Currency = balances::Module<Test>;
+}
+
+type Oracle = Module<Test>;
+type Timestamp = timestamp::Module<Test>;
+
+fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+	let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+	t.extend(timestamp::GenesisConfig::<Test>{
+		minimum_period: 5,
+	}.build_storage().unwrap().0);
+	t.extend(GenesisConfig::<Test>{
+		feeders: vec![0],
+		max_age: 100,
+	}.build_storage().unwrap().0);
+	t.into()
+}
+
+#[test]
+fn only_feeders_should_appraise() {
+	with_externalities(&mut new_test_ext(), || {
+		let asset = H256::from([1u8; 32]);
+		assert_noop!(Oracle::appraise(Origin::signed(1), asset, 50), "Only feeders can appraise assets");
+
+		assert_ok!(Oracle::appraise(Origin::signed(0), asset, 50));
+		let appraisal = Oracle::get_appraisal(asset).unwrap();
+		assert_eq!(appraisal.value, 50);
+		assert_eq!(appraisal.feeder, 0);
+		assert_eq!(Oracle::value_of(&asset), Some(50));
+
+		// A newer appraisal replaces the old one
+		assert_ok!(Oracle::appraise(Origin::signed(0), asset, 40));
+		assert_eq!(Oracle::value_of(&asset), Some(40));
+	});
+}
+
+#[test]
+fn root_should_manage_feeders() {
+	with_externalities(&mut new_test_ext(), || {
+		let asset = H256::from([1u8; 32]);
+		assert!(Oracle::add_feeder(Origin::signed(0), 1).is_err());
+
+		assert_ok!(Oracle::add_feeder(Origin::ROOT, 1));
+		assert_noop!(Oracle::add_feeder(Origin::ROOT, 1), "This account is already a feeder");
+		assert_ok!(Oracle::appraise(Origin::signed(1), asset, 50));
+
+		assert_ok!(Oracle::remove_feeder(Origin::ROOT, 1));
+		assert_noop!(Oracle::remove_feeder(Origin::ROOT, 1), "This account is not a feeder");
+		assert_noop!(Oracle::appraise(Origin::signed(1), asset, 60), "Only feeders can appraise assets");
+		assert_eq!(Oracle::feeders(), vec![0]);
+	});
+}
+
+#[test]
+fn stale_appraisals_should_have_no_value() {
+	with_externalities(&mut new_test_ext(), || {
+		let asset = H256::from([1u8; 32]);
+		assert_eq!(Oracle::value_of(&asset), None);
+
+		Timestamp::set_timestamp(10);
+		assert_ok!(Oracle::appraise(Origin::signed(0), asset, 50));
+
+		Timestamp::set_timestamp(110);
+		assert_eq!(Oracle::value_of(&asset), Some(50));
+		Timestamp::set_timestamp(111);
+		assert_eq!(Oracle::value_of(&asset), None);
+		// The appraisal is kept, it just can't be relied on
+		assert!(Oracle::get_appraisal(asset).is_some());
+	});
+}
+
+#[test]
+fn tokens_should_fall_back_to_their_collection() {
+	with_externalities(&mut new_test_ext(), || {
+		let token_id = H256::from([1u8; 32]);
+		let collection = H256::from([2u8; 32]);
+		assert_ok!(Oracle::appraise(Origin::signed(0), collection, 30));
+		assert_eq!(Oracle::value_of(&token_id), None);
+
+		assert_noop!(Oracle::set_collection(Origin::signed(1), token_id, Some(collection)), "Only feeders can set collections");
+		assert_ok!(Oracle::set_collection(Origin::signed(0), token_id, Some(collection)));
+		assert_eq!(Oracle::value_of(&token_id), Some(30));
+
+		// Its own appraisal wins while it's current
+		Timestamp::set_timestamp(50);
+		assert_ok!(Oracle::appraise(Origin::signed(0), token_id, 45));
+		assert_eq!(Oracle::value_of(&token_id), Some(45));
+		Timestamp::set_timestamp(101);
+		assert_eq!(Oracle::value_of(&token_id), Some(45));
+		Timestamp::set_timestamp(151);
+		assert_eq!(Oracle::value_of(&token_id), None);
+
+		assert_ok!(Oracle::set_collection(Origin::signed(0), token_id, None));
+		assert_eq!(Oracle::collection_of(token_id), None);
+	});
+}
@@ -0,0 +1,189 @@
+/// Order book: indexes open debt requests and loan offers by their terms, and matches
+/// them at the end of every block. Requests and offers opt in by being listed, and are
+/// dropped from the book once they can no longer be matched.
+
+use support::{decl_module, decl_storage, decl_event, StorageValue, StorageMap, dispatch::Result, ensure};
+use system::ensure_signed;
+use super::debt::{self, BalanceOf, DebtStatus, InterestType};
+use rstd::prelude::*;
+
+#[cfg(test)]
+mod test;
+
+/// The module's configuration trait.
+pub trait Trait: debt::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+/// Only requests and offers with the same terms can match:
+/// (principal, term_length, interest_period, interest_type)
+pub type BookKey<Balance, Moment> = (Balance, Moment, Moment, InterestType);
+type BookKeyOf<T> = BookKey<BalanceOf<T>, <T as timestamp::Trait>::Moment>;
+
+/// Upper bound on matches tried in one block, failed ones included. The rest carry over to the next one
+const MAX_MATCHES_PER_BLOCK: u32 = 32;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as OrderBook {
+		// (interest_rate, debt_id) of listed requests, highest rate first
+		Requests get(get_requests): map BookKey<BalanceOf<T>, T::Moment> => Vec<(u64, T::Hash)>;
+		// (interest_rate, offer_id) of listed offers, lowest rate first
+		Offers get(get_offers): map BookKey<BalanceOf<T>, T::Moment> => Vec<(u64, T::Hash)>;
+		// Books that got new entries, to be matched in on_finalize
+		PendingBooks get(get_pending_books): Vec<BookKey<BalanceOf<T>, T::Moment>>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event<T>() = default;
+
+		// Requestors list a collateralized request, its interest rate is the most they will pay
+		pub fn list_request(origin, debt_id: T::Hash) {
+			let sender = ensure_signed(origin)?;
+			ensure!(<debt::Module<T>>::debt_exists(debt_id), "This debt does not exist");
+			let debt = <debt::Module<T>>::get_debt(debt_id);
+
+			ensure!(debt.requestor == sender, "Only the requestor can list this debt request");
+			ensure!(Self::is_open_request(debt_id), "This debt request cannot be matched");
+
+			let key = (debt.principal, debt.term_length, debt.interest_period, debt.interest_type);
+			let mut requests = Self::get_requests(&key);
+			ensure!(!requests.iter().any(|&(_, id)| id == debt_id), "This debt request is already listed");
+
+			let position = requests.iter().position(|&(rate, _)| rate < debt.interest_rate).unwrap_or(requests.len());
+			requests.insert(position, (debt.interest_rate, debt_id));
+			<Requests<T>>::insert(&key, requests);
+			Self::mark_pending(key);
+
+			Self::deposit_event(RawEvent::RequestListed(sender, debt_id));
+		}
+
+		// Creditors list a loan offer, its interest rate is the least they will lend at
+		pub fn list_offer(origin, offer_id: T::Hash) {
+			let sender = ensure_signed(origin)?;
+			let offer = <debt::Module<T>>::get_offer(offer_id).ok_or("This loan offer does not exist")?;
+
+			ensure!(offer.creditor == sender, "Only the creditor can list this loan offer");
+			ensure!(Self::is_open_offer(offer_id), "This loan offer cannot be matched");
+
+			let key = (offer.principal, offer.term_length, offer.interest_period, offer.interest_type);
+			let mut offers = Self::get_offers(&key);
+			ensure!(!offers.iter().any(|&(_, id)| id == offer_id), "This loan offer is already listed");
+
+			let position = offers.iter().position(|&(rate, _)| rate > offer.interest_rate).unwrap_or(offers.len());
+			offers.insert(position, (offer.interest_rate, offer_id));
+			<Offers<T>>::insert(&key, offers);
+			Self::mark_pending(key);
+
+			Self::deposit_event(RawEvent::OfferListed(sender, offer_id));
+		}
+
+		// Match the best offer against the best request of every book that changed,
+		// until the rates no longer cross
+		fn on_finalize(_n: T::BlockNumber) {
+			let mut attempts = 0;
+			let mut pending = Vec::new();
+
+			for key in <PendingBooks<T>>::take() {
+				if attempts >= MAX_MATCHES_PER_BLOCK {
+					pending.push(key);
+					continue;
+				}
+				attempts += Self::match_book(&key, MAX_MATCHES_PER_BLOCK - attempts);
+				if attempts >= MAX_MATCHES_PER_BLOCK {
+					pending.push(key);
+				}
+			}
+
+			if !pending.is_empty() {
+				<PendingBooks<T>>::put(pending);
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// Matches one book, returns how many matches were tried
+	fn match_book(key: &BookKeyOf<T>, max_attempts: u32) -> u32 {
+		let mut requests = Self::get_requests(key);
+		let mut offers = Self::get_offers(key);
+		let mut attempts = 0;
+
+		while attempts < max_attempts {
+			// drop whatever was cancelled, expired or filled elsewhere since it was listed
+			while !requests.is_empty() && !Self::is_open_request(requests[0].1) {
+				requests.remove(0);
+			}
+			while !offers.is_empty() && !Self::is_open_offer(offers[0].1) {
+				offers.remove(0);
+			}
+			if requests.is_empty() || offers.is_empty() {
+				break;
+			}
+
+			let (request_rate, debt_id) = requests[0];
+			let (offer_rate, offer_id) = offers[0];
+			if offer_rate > request_rate {
+				break;
+			}
+
+			attempts += 1;
+			if <debt::Module<T>>::fulfill_from_offer(debt_id, offer_id).is_ok() {
+				requests.remove(0);
+				offers.remove(0);
+				Self::deposit_event(RawEvent::Matched(debt_id, offer_id));
+			} else if Self::is_open_offer(offer_id) {
+				// the offer could still fund a loan, so it's the request that can't be funded
+				requests.remove(0);
+			} else {
+				offers.remove(0);
+			}
+		}
+
+		<Requests<T>>::insert(key, requests);
+		<Offers<T>>::insert(key, offers);
+
+		attempts
+	}
+
+	fn mark_pending(key: BookKeyOf<T>) {
+		let mut pending = Self::get_pending_books();
+		if !pending.contains(&key) {
+			pending.push(key);
+			<PendingBooks<T>>::put(pending);
+		}
+	}
+
+	fn is_open_request(debt_id: T::Hash) -> bool {
+		if !<debt::Module<T>>::debt_exists(debt_id) {
+			return false;
+		}
+		let debt = <debt::Module<T>>::get_debt(debt_id);
+		let now = <timestamp::Module<T>>::get();
+
+		debt.status == DebtStatus::Requested && debt.request_expiry >= now
+			&& <debt::Module<T>>::is_collateralized(debt_id)
+			&& <debt::Module<T>>::is_within_loan_to_value(debt_id, &debt)
+	}
+
+	fn is_open_offer(offer_id: T::Hash) -> bool {
+		let now = <timestamp::Module<T>>::get();
+		match <debt::Module<T>>::get_offer(offer_id) {
+			Some(offer) => offer.offer_expiry >= now && <debt::Module<T>>::is_offer_funded(offer_id, &offer),
+			None => false,
+		}
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+	{
+		RequestListed(AccountId, Hash),
+		OfferListed(AccountId, Hash),
+		// debt_id, offer_id
+		Matched(Hash, Hash),
+	}
+);
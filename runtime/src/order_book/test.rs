@@ -0,0 +1,263 @@
+#[cfg(test)] // allows us to compile code, based on the "test" flag.
+
+use super::*;
+use super::super::erc721;
+use support::{impl_outer_origin, assert_ok, assert_noop};
+use runtime_io::with_externalities;
+use primitives::{H256, Blake2Hasher};
+use runtime_primitives::{
+	BuildStorage,
+	traits::{IdentityLookup, BlakeTwo256, OnFinalize},
+	testing::{Digest, DigestItem, Header}
+};
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+impl system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type Digest = Digest;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type Log = DigestItem;
+}
+
+impl balances::Trait for Test {
+	type Balance = u64;
+	type OnFreeBalanceZero = ();
+	type OnNewAccount = ();
+	type Event = ();
+	type TransactionPayment = ();
+	type TransferPayment = ();
+	type DustRemoval = ();
+}
+
+impl timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+}
+
+impl erc721::Trait for Test {
+	type Event = ();
+	type EscrowAuthority = debt::Module<Test>;
+	type OnTransfer = debt::Module<Test>;
+}
+
+impl debt::Trait for Test {
+	type Event = ();
+	type Currency = balances::Module<Test>;
+	type InterestModel = debt::PeriodicInterest;
+	type Collateral = erc721::Module<Test>;
+	type Notes = erc721::Module<Test>;
+	type Liquidator = ();
+	type Appraiser = ();
+}
+
+impl Trait for Test {
+	type Event = ();
+}
+
+type OrderBook = Module<Test>;
+type Debt = debt::Module<Test>;
+type Balance = balances::Module<Test>;
+type ERC = erc721::Module<Test>;
+
+fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+	test_ext(0)
+}
+
+// Debts can borrow up to `max_loan_to_value` of their collateral's value, 0 for no cap
+fn test_ext(max_loan_to_value: u64) -> runtime_io::TestExternalities<Blake2Hasher> {
+	let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+	t.extend(balances::GenesisConfig::<Test>{
+		balances: vec![(0, 100),(1, 100),(2, 100),(3, 100)],
+		transaction_base_fee: 0,
+		transaction_byte_fee: 0,
+		transfer_fee: 0,
+		creation_fee: 0,
+		existential_deposit: 0,
+		vesting: vec![],
+	}.build_storage().unwrap().0);
+	t.extend(timestamp::GenesisConfig::<Test>{
+		minimum_period: 5,
+	}.build_storage().unwrap().0);
+	t.extend(debt::GenesisConfig::<Test>{
+		grace_period: 0,
+		late_fee: 0,
+		late_fee_rate: 0,
+		max_loan_to_value,
+		maintenance_loan_to_value: 0,
+		margin_call_period: 0,
+	}.build_storage().unwrap().0);
+	t.into()
+}
+
+// Borrows 50 over a term of 3, at up to `rate`, against a new token
+fn request(who: u64, rate: u64) -> H256 {
+	ERC::create_token(Origin::signed(who));
+	let token_id = ERC::token_of_owner_by_index((who, ERC::balance_of(who) - 1));
+	assert_ok!(Debt::borrow_against(Origin::signed(who), token_id, who, 10, 50, rate, 1, 3, InterestType::Simple, 1));
+	Debt::get_debt_id(Debt::get_total_debts() - 1)
+}
+
+// Offers 50 over a term of 3 at `rate`
+fn offer(who: u64, rate: u64) -> H256 {
+	assert_ok!(Debt::offer(Origin::signed(who), 10, 50, rate, 1, 3, InterestType::Simple));
+	Debt::get_offer_id(Debt::get_total_offers() - 1)
+}
+
+#[test]
+fn matches_crossing_rates() {
+	with_externalities(&mut new_test_ext(), || {
+		let debt_id = request(1, 500);
+		let offer_id = offer(2, 400);
+
+		assert_noop!(OrderBook::list_request(Origin::signed(2), debt_id), "Only the requestor can list this debt request");
+		assert_ok!(OrderBook::list_request(Origin::signed(1), debt_id));
+		assert_ok!(OrderBook::list_offer(Origin::signed(2), offer_id));
+		assert_noop!(OrderBook::list_offer(Origin::signed(2), offer_id), "This loan offer is already listed");
+
+		<OrderBook as OnFinalize<u64>>::on_finalize(1);
+
+		// the debt takes the offer's rate
+		let debt = Debt::get_debt(debt_id);
+		assert_eq!(debt.status, DebtStatus::Active);
+		assert_eq!(debt.creditor, 2);
+		assert_eq!(debt.interest_rate, 400);
+		assert_eq!(Balance::free_balance(&1), 150);
+		assert_eq!(Balance::reserved_balance(&2), 0);
+		assert!(OrderBook::get_pending_books().is_empty());
+	});
+}
+
+#[test]
+fn does_not_match_above_max_rate() {
+	with_externalities(&mut new_test_ext(), || {
+		let debt_id = request(1, 300);
+		let offer_id = offer(2, 400);
+		assert_ok!(OrderBook::list_request(Origin::signed(1), debt_id));
+		assert_ok!(OrderBook::list_offer(Origin::signed(2), offer_id));
+
+		<OrderBook as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Debt::get_debt(debt_id).status, DebtStatus::Requested);
+		assert_eq!(OrderBook::get_requests((50, 3, 1, InterestType::Simple)).len(), 1);
+		assert_eq!(OrderBook::get_offers((50, 3, 1, InterestType::Simple)).len(), 1);
+	});
+}
+
+#[test]
+fn matches_best_rates_first() {
+	with_externalities(&mut new_test_ext(), || {
+		let low_request = request(1, 300);
+		let high_request = request(0, 600);
+		let cheap_offer = offer(2, 200);
+		let dear_offer = offer(3, 500);
+		for &id in [low_request, high_request].iter() {
+			let requestor = Debt::get_debt(id).requestor;
+			assert_ok!(OrderBook::list_request(Origin::signed(requestor), id));
+		}
+		assert_ok!(OrderBook::list_offer(Origin::signed(3), dear_offer));
+		assert_ok!(OrderBook::list_offer(Origin::signed(2), cheap_offer));
+
+		<OrderBook as OnFinalize<u64>>::on_finalize(1);
+
+		// cheapest offer funds the most eager request, the rest doesn't cross
+		assert_eq!(Debt::get_debt(high_request).creditor, 2);
+		assert_eq!(Debt::get_debt(low_request).status, DebtStatus::Requested);
+		assert!(Debt::get_offer(dear_offer).is_some());
+	});
+}
+
+#[test]
+fn match_book_counts_attempts() {
+	with_externalities(&mut new_test_ext(), || {
+		let key = (50, 3, 1, InterestType::Simple);
+		for &(who, rate) in [(0, 500), (1, 500)].iter() {
+			let debt_id = request(who, rate);
+			assert_ok!(OrderBook::list_request(Origin::signed(who), debt_id));
+		}
+		for &who in [2, 3].iter() {
+			let offer_id = offer(who, 400);
+			assert_ok!(OrderBook::list_offer(Origin::signed(who), offer_id));
+		}
+
+		// stops at the limit, and doesn't count the books running out
+		assert_eq!(OrderBook::match_book(&key, 1), 1);
+		assert_eq!(OrderBook::get_requests(&key).len(), 1);
+		assert_eq!(OrderBook::match_book(&key, 5), 1);
+		assert_eq!(OrderBook::match_book(&key, 5), 0);
+		assert!(OrderBook::get_offers(&key).is_empty());
+	});
+}
+
+#[test]
+fn skips_cancelled_entries() {
+	with_externalities(&mut new_test_ext(), || {
+		let debt_id = request(1, 500);
+		let cancelled_offer = offer(3, 100);
+		let offer_id = offer(2, 400);
+		assert_ok!(OrderBook::list_request(Origin::signed(1), debt_id));
+		assert_ok!(OrderBook::list_offer(Origin::signed(3), cancelled_offer));
+		assert_ok!(OrderBook::list_offer(Origin::signed(2), offer_id));
+		assert_ok!(Debt::cancel_offer(Origin::signed(3), cancelled_offer));
+
+		<OrderBook as OnFinalize<u64>>::on_finalize(1);
+
+		assert_eq!(Debt::get_debt(debt_id).creditor, 2);
+		assert!(OrderBook::get_offers((50, 3, 1, InterestType::Simple)).is_empty());
+	});
+}
+
+#[test]
+fn unfundable_requests_do_not_drain_offers() {
+	with_externalities(&mut new_test_ext(), || {
+		// pays out to account 9, which doesn't exist
+		ERC::create_token(Origin::signed(1));
+		let token_id = ERC::token_by_index(0);
+		assert_ok!(Debt::borrow_against(Origin::signed(1), token_id, 9, 10, 50, 500, 1, 3, InterestType::Simple, 1));
+		let bad_request = Debt::get_debt_id(0);
+		let good_request = request(0, 400);
+		let first_offer = offer(2, 300);
+		let second_offer = offer(3, 300);
+
+		assert_ok!(OrderBook::list_request(Origin::signed(1), bad_request));
+		assert_ok!(OrderBook::list_request(Origin::signed(0), good_request));
+		assert_ok!(OrderBook::list_offer(Origin::signed(2), first_offer));
+		assert_ok!(OrderBook::list_offer(Origin::signed(3), second_offer));
+
+		<OrderBook as OnFinalize<u64>>::on_finalize(1);
+
+		// only the bad request is dropped, the good one still gets the best offer
+		assert_eq!(Debt::get_debt(bad_request).status, DebtStatus::Requested);
+		assert_eq!(Debt::get_debt(good_request).creditor, 2);
+		assert_eq!(OrderBook::get_requests((50, 3, 1, InterestType::Simple)), vec![]);
+		assert_eq!(OrderBook::get_offers((50, 3, 1, InterestType::Simple)), vec![(300, second_offer)]);
+	});
+}
+
+#[test]
+fn requests_over_the_loan_to_value_cap_cannot_be_listed() {
+	with_externalities(&mut test_ext(5_000), || {
+		// 50 against 60 reserved is over the 50% cap, against 100 it isn't
+		assert_ok!(Debt::borrow(Origin::signed(0), 0, 10, 50, 500, 1, 3, InterestType::Simple, 1));
+		let thin_request = Debt::get_debt_id(0);
+		assert_ok!(Debt::collateralize_balance(Origin::signed(0), thin_request, 60));
+		assert_noop!(OrderBook::list_request(Origin::signed(0), thin_request), "This debt request cannot be matched");
+
+		assert_ok!(Debt::borrow(Origin::signed(1), 1, 10, 50, 500, 1, 3, InterestType::Simple, 1));
+		let backed_request = Debt::get_debt_id(1);
+		assert_ok!(Debt::collateralize_balance(Origin::signed(1), backed_request, 100));
+		assert_ok!(OrderBook::list_request(Origin::signed(1), backed_request));
+	});
+}
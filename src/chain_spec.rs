@@ -1,7 +1,7 @@
 use primitives::{ed25519, sr25519, Pair};
 use node_template_runtime::{
 	AccountId, GenesisConfig, ConsensusConfig, TimestampConfig, BalancesConfig,
-	SudoConfig, IndicesConfig,
+	SudoConfig, IndicesConfig, DebtConfig, AuctionConfig, Decay, OracleConfig,
 };
 use substrate_service;
 
@@ -113,7 +113,25 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, endowed_accounts: Vec<
 			vesting: vec![],
 		}),
 		sudo: Some(SudoConfig {
-			key: root_key,
+			key: root_key.clone(),
+		}),
+		debt: Some(DebtConfig {
+			grace_period: 24 * 60 * 60, // a day to catch up on a missed deadline
+			late_fee: 0,
+			late_fee_rate: 100,
+			max_loan_to_value: 7_000,
+			maintenance_loan_to_value: 8_500,
+			margin_call_period: 24 * 60 * 60, // a day to meet a margin call
+		}),
+		auction: Some(AuctionConfig {
+			auction_length: 24 * 60 * 6, // a day of 10 second blocks
+			dutch_markup: 15_000,
+			dutch_floor: 5_000,
+			dutch_decay: Decay::Exponential(5), // roughly halves every 1400 blocks
+		}),
+		oracle: Some(OracleConfig {
+			feeders: vec![root_key],
+			max_age: 7 * 24 * 60 * 60, // appraisals are good for a week
 		}),
 	}
 }
@@ -5,6 +5,7 @@
 
 mod chain_spec;
 mod service;
+mod rpc;
 mod cli;
 
 pub use substrate_cli::{VersionInfo, IntoExit, error};
@@ -0,0 +1,124 @@
+//! JSON-RPC methods for debts and erc721 tokens, answered by the runtime's `DebtApi`
+//! at the best block. Served next to the node's own RPC, on `DEFAULT_RPC_ADDRESS` unless
+//! the `DEBT_RPC_ADDRESS` environment variable names another one
+
+use std::{env, io, net::SocketAddr, sync::Arc};
+use jsonrpc_core::{Error, ErrorCode, IoHandler, Result};
+use jsonrpc_derive::rpc;
+use jsonrpc_http_server::{Server, ServerBuilder};
+use primitives::{Blake2Hasher, H256};
+use substrate_client::{self as client, Client, runtime_api::ProvideRuntimeApi};
+use node_template_runtime::{AccountId, Balance, Moment, DebtStatus, debt_api::DebtApi, opaque::{Block, BlockId}};
+
+/// Where the debt and erc721 methods are served by default
+pub const DEFAULT_RPC_ADDRESS: &str = "127.0.0.1:9934";
+/// Overrides `DEFAULT_RPC_ADDRESS`, e.g. so a second node on the same host can serve them too
+pub const RPC_ADDRESS_VAR: &str = "DEBT_RPC_ADDRESS";
+
+/// The address to serve on
+pub fn address() -> String {
+	env::var(RPC_ADDRESS_VAR).unwrap_or_else(|_| DEFAULT_RPC_ADDRESS.into())
+}
+
+#[rpc]
+pub trait DebtRpc {
+	#[rpc(name = "debt_byRequestor")]
+	fn debts_by_requestor(&self, who: AccountId) -> Result<Vec<H256>>;
+
+	#[rpc(name = "debt_byBeneficiary")]
+	fn debts_by_beneficiary(&self, who: AccountId) -> Result<Vec<H256>>;
+
+	#[rpc(name = "debt_byCreditor")]
+	fn debts_by_creditor(&self, who: AccountId) -> Result<Vec<H256>>;
+
+	/// Debts currently in `status`. Reads every debt ever created, so it gets slower over time
+	#[rpc(name = "debt_byStatus")]
+	fn debts_by_status(&self, status: DebtStatus) -> Result<Vec<H256>>;
+
+	/// Tokens escrowed for a debt, and the balance reserved for it
+	#[rpc(name = "debt_collateral")]
+	fn collateral_of(&self, debt_id: H256) -> Result<(Vec<H256>, Balance)>;
+
+	/// (principal, interest, total) to pay off a debt at `at`, or now
+	#[rpc(name = "debt_quoteOutstanding")]
+	fn quote_outstanding(&self, debt_id: H256, at: Option<Moment>) -> Result<Option<(Balance, Balance, Balance)>>;
+
+	#[rpc(name = "erc721_tokensOf")]
+	fn tokens_of(&self, owner: AccountId) -> Result<Vec<H256>>;
+}
+
+pub struct Debt<B, E, RA> {
+	client: Arc<Client<B, E, Block, RA>>,
+}
+
+impl<B, E, RA> Debt<B, E, RA> {
+	pub fn new(client: Arc<Client<B, E, Block, RA>>) -> Self {
+		Debt { client }
+	}
+}
+
+impl<B, E, RA> Debt<B, E, RA> where
+	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+{
+	fn best_block(&self) -> Result<BlockId> {
+		let info = self.client.info().map_err(error)?;
+		Ok(BlockId::hash(info.chain.best_hash))
+	}
+}
+
+fn error<D: std::fmt::Debug>(e: D) -> Error {
+	Error {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to query the runtime".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<B, E, RA> DebtRpc for Debt<B, E, RA> where
+	B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: DebtApi<Block, AccountId, H256, Balance, Moment>,
+{
+	fn debts_by_requestor(&self, who: AccountId) -> Result<Vec<H256>> {
+		self.client.runtime_api().debts_by_requestor(&self.best_block()?, who).map_err(error)
+	}
+
+	fn debts_by_beneficiary(&self, who: AccountId) -> Result<Vec<H256>> {
+		self.client.runtime_api().debts_by_beneficiary(&self.best_block()?, who).map_err(error)
+	}
+
+	fn debts_by_creditor(&self, who: AccountId) -> Result<Vec<H256>> {
+		self.client.runtime_api().debts_by_creditor(&self.best_block()?, who).map_err(error)
+	}
+
+	fn debts_by_status(&self, status: DebtStatus) -> Result<Vec<H256>> {
+		self.client.runtime_api().debts_by_status(&self.best_block()?, status).map_err(error)
+	}
+
+	fn collateral_of(&self, debt_id: H256) -> Result<(Vec<H256>, Balance)> {
+		self.client.runtime_api().collateral_of(&self.best_block()?, debt_id).map_err(error)
+	}
+
+	fn quote_outstanding(&self, debt_id: H256, at: Option<Moment>) -> Result<Option<(Balance, Balance, Balance)>> {
+		self.client.runtime_api().quote_outstanding(&self.best_block()?, debt_id, at).map_err(error)
+	}
+
+	fn tokens_of(&self, owner: AccountId) -> Result<Vec<H256>> {
+		self.client.runtime_api().tokens_of(&self.best_block()?, owner).map_err(error)
+	}
+}
+
+/// Starts serving the methods over http on `address`, they stop when the returned server is dropped
+pub fn start_http<B, E, RA>(client: Arc<Client<B, E, Block, RA>>, address: &str) -> io::Result<Server> where
+	Debt<B, E, RA>: DebtRpc,
+{
+	let address: SocketAddr = address.parse().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+	let mut handler = IoHandler::new();
+	handler.extend_with(Debt::new(client).to_delegate());
+	ServerBuilder::new(handler).threads(1).start_http(&address)
+}
@@ -3,7 +3,7 @@
 #![warn(unused_extern_crates)]
 
 use std::sync::Arc;
-use log::info;
+use log::{info, warn};
 use transaction_pool::{self, txpool::{Pool as TransactionPool}};
 use node_template_runtime::{self, GenesisConfig, opaque::Block, RuntimeApi};
 use substrate_service::{
@@ -19,6 +19,7 @@ use inherents::InherentDataProviders;
 use network::construct_simple_protocol;
 use substrate_executor::native_executor_instance;
 use substrate_service::construct_service_factory;
+use crate::rpc;
 
 pub use substrate_executor::NativeExecutor;
 // Our native executor instance.
@@ -57,6 +58,17 @@ construct_service_factory! {
 			},
 		AuthoritySetup = {
 			|service: Self::FullService, executor: TaskExecutor, key: Option<Arc<Pair>>| {
+				let address = rpc::address();
+				match rpc::start_http(service.client(), &address) {
+					Ok(rpc) => {
+						info!("Debt RPC listening on {}", address);
+						// runs until the node exits
+						std::thread::spawn(move || rpc.wait());
+					},
+					// e.g. another node on this host took the address, this one runs without it
+					Err(e) => warn!("Unable to start the debt RPC on {}: {}", address, e),
+				}
+
 				if let Some(key) = key {
 					info!("Using authority key {}", key.public());
 					let proposer = Arc::new(ProposerFactory {